@@ -1,5 +1,6 @@
 pub mod context;
 pub mod data;
+pub mod gc;
 pub mod global;
 pub mod lvalue;
 pub mod module;
@@ -3,7 +3,7 @@ use std::{cell::RefCell, rc::Rc};
 use crate::statement::CodeExecError;
 
 use super::{
-    data::{MemData, MemDataRc, Mess},
+    data::{Layout, MemData, MemDataRc, Mess},
     global::Global,
     variable::VarType,
 };
@@ -19,17 +19,25 @@ pub struct Context {
 
 impl Context {
     pub fn new(parent: &ContextRc) -> Self {
+        Context::with_mess(parent, Mess::new())
+    }
+    fn with_mess(parent: &ContextRc, mess: Mess) -> Self {
         let global = parent.borrow().global.clone();
-        let mess = MemData::new_rc(MemData::Mess(Mess::new()));
         Context {
             global,
             parent: Some(parent.clone()),
-            mess,
+            mess: MemData::new_rc(MemData::Mess(mess)),
         }
     }
     pub fn new_rc(parent: &ContextRc) -> ContextRc {
         Rc::new(RefCell::new(Context::new(parent)))
     }
+    pub fn new_rc_with_layout(parent: &ContextRc, layout: &Rc<Layout>) -> ContextRc {
+        Rc::new(RefCell::new(Context::with_mess(
+            parent,
+            Mess::with_layout(layout),
+        )))
+    }
     pub fn root(global: &GlobalRc) -> Self {
         let mess = MemData::new_rc(MemData::Mess(Mess::new()));
         Context {
@@ -74,6 +82,10 @@ impl Context {
         self.mess.clone()
     }
 
+    pub fn get_parent(&self) -> Option<ContextRc> {
+        self.parent.clone()
+    }
+
     pub fn get_global(&self) -> GlobalRc {
         self.global.clone()
     }
@@ -83,9 +95,10 @@ impl Context {
     }
 
     pub fn get_symbol(&self, name: &str) -> Option<VarType> {
-        let data_item = self.get_mess();
-        if data_item.borrow().has(&name) {
-            return Some(data_item.borrow().get(&name));
+        if let MemData::Mess(mess) = &*self.mess.borrow() {
+            if let Some(var) = mess.get(name) {
+                return Some(var);
+            }
         }
         if let Some(parent) = &self.parent {
             parent.borrow().get_symbol(name)
@@ -95,8 +108,7 @@ impl Context {
     }
 
     pub fn has_symbol(&self, name: &str) -> bool {
-        let data_item = self.get_mess();
-        if data_item.borrow().has(&name) {
+        if self.mess.borrow().has(name) {
             return true;
         }
         if let Some(parent) = &self.parent {
@@ -136,4 +148,41 @@ impl Context {
             ))
         }
     }
+    /// Reads the variable in `slot` of this scope, looking `name` up in outer
+    /// scopes while the slot is unset.
+    pub fn get_slot(&self, slot: usize, name: &str) -> Result<VarType, CodeExecError> {
+        if let MemData::Mess(mess) = &*self.mess.borrow() {
+            if let Some(var) = mess.slot(slot) {
+                return Ok(var.clone());
+            }
+        }
+        match self
+            .parent
+            .as_ref()
+            .and_then(|p| p.borrow().get_symbol(name))
+        {
+            Some(var) => Ok(var),
+            None => self.get_symbol_or_err(name),
+        }
+    }
+
+    /// Writes the variable in `slot` of this scope, with the same rules as
+    /// `set_symbol`: an unset slot defers to an outer scope that has `name`.
+    pub fn set_slot(&self, slot: usize, name: &str, value: VarType) {
+        if let MemData::Mess(mess) = &mut *self.mess.borrow_mut() {
+            if mess.slot(slot).is_some() {
+                mess.set_slot(slot, value);
+                return;
+            }
+        }
+        if let Some(parent) = &self.parent {
+            if parent.borrow().has_symbol(name) {
+                parent.borrow().set_symbol(name, value);
+                return;
+            }
+        }
+        if let MemData::Mess(mess) = &mut *self.mess.borrow_mut() {
+            mess.set_slot(slot, value);
+        }
+    }
 }
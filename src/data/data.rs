@@ -1,6 +1,9 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
 
-use crate::statement::CodeExecError;
+use crate::{
+    parser::literal::string_literal,
+    statement::{CodeExecError, ErrorOrigin},
+};
 
 use super::{context::Context, node::Node, variable::VarType};
 
@@ -59,32 +62,117 @@ impl MemData {
     }
 }
 
+/// Names of the slots of a `Mess`. Compiled code addresses variables by slot
+/// index, so a layout only ever grows: existing indices never move.
+#[derive(Debug, Clone, Default)]
+pub struct Layout {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+}
+
+impl Layout {
+    pub fn new() -> Self {
+        Layout::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    pub fn name(&self, slot: usize) -> &str {
+        &self.names[slot]
+    }
+
+    /// Returns the slot of `name`, adding it if it is new.
+    pub fn add(&mut self, name: &str) -> usize {
+        if let Some(slot) = self.index(name) {
+            return slot;
+        }
+        self.names.push(name.to_owned());
+        self.index.insert(name.to_owned(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+}
+
+/// Variables of one scope. A slot is `None` until its variable is first set
+/// in this scope, so a reserved slot does not shadow outer scopes.
 #[derive(Debug, Clone)]
 pub struct Mess {
-    members: HashMap<String, VarType>,
+    layout: Rc<Layout>,
+    slots: Vec<Option<VarType>>,
 }
 
 impl Mess {
     pub fn new() -> Self {
+        Mess::with_layout(&Rc::new(Layout::new()))
+    }
+
+    pub fn with_layout(layout: &Rc<Layout>) -> Self {
         Mess {
-            members: HashMap::new(),
+            layout: layout.clone(),
+            slots: vec![None; layout.len()],
         }
     }
 
+    pub fn layout(&self) -> &Rc<Layout> {
+        &self.layout
+    }
+
+    /// Switches to `layout`, which must extend the current one.
+    pub fn adopt(&mut self, layout: &Rc<Layout>) {
+        debug_assert!(layout.len() >= self.layout.len());
+        self.layout = layout.clone();
+        self.slots.resize(layout.len(), None);
+    }
+
     pub fn has(&self, name: &str) -> bool {
-        self.members.contains_key(name)
+        self.layout
+            .index(name)
+            .is_some_and(|slot| self.slots[slot].is_some())
     }
 
     pub fn get(&self, name: &str) -> Option<VarType> {
-        if let Some(var) = self.members.get(name) {
-            return Some(var.clone());
-        } else {
-            None
-        }
+        self.slot(self.layout.index(name)?).cloned()
     }
 
     pub fn set(&mut self, name: &str, var: VarType) {
-        self.members.insert(name.to_string(), var);
+        let slot = match self.layout.index(name) {
+            Some(slot) => slot,
+            None => {
+                let slot = Rc::make_mut(&mut self.layout).add(name);
+                self.slots.resize(self.layout.len(), None);
+                slot
+            }
+        };
+        self.slots[slot] = Some(var);
+    }
+
+    #[inline]
+    pub fn slot(&self, slot: usize) -> Option<&VarType> {
+        self.slots[slot].as_ref()
+    }
+
+    #[inline]
+    pub fn set_slot(&mut self, slot: usize, var: VarType) {
+        self.slots[slot] = Some(var);
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &VarType> {
+        self.slots.iter().flatten()
+    }
+
+    /// Unsets every variable and returns their values.
+    pub fn clear(&mut self) -> Vec<VarType> {
+        let slots = std::mem::replace(&mut self.slots, vec![None; self.layout.len()]);
+        slots.into_iter().flatten().collect()
     }
 }
 
@@ -93,34 +181,131 @@ pub struct Tuple {
     pub items: Vec<VarType>,
 }
 
-#[derive(Debug, Clone)]
+/// Members of a `lol`, kept in the order their keys were first set. Setting
+/// a key again keeps its place; a key that is deleted and set again goes to
+/// the end.
+#[derive(Clone)]
 pub struct Object {
-    members: HashMap<String, VarType>,
+    /// Position of each key in `entries`.
+    index: HashMap<String, usize>,
+    /// Removed members leave a `None` behind until there are as many of them
+    /// as members left.
+    entries: Vec<Option<(String, VarType)>>,
+    /// Where the error was raised, if the object describes one caught by
+    /// `oops`.
+    origin: Option<Rc<ErrorOrigin>>,
 }
 
 impl Object {
     pub fn new() -> Self {
         Object {
-            members: HashMap::new(),
+            index: HashMap::new(),
+            entries: Vec::new(),
+            origin: None,
+        }
+    }
+
+    /// An object describing an error raised at `origin`.
+    pub fn with_origin(origin: ErrorOrigin) -> Self {
+        Object {
+            origin: Some(Rc::new(origin)),
+            ..Object::new()
+        }
+    }
+
+    pub fn origin(&self) -> Option<Rc<ErrorOrigin>> {
+        self.origin.clone()
+    }
+
+    /// A list in the layout of `ll`: items under "0", "1", ... and their
+    /// count under `len`.
+    pub fn list(items: impl IntoIterator<Item = VarType>) -> Self {
+        let mut obj = Object::new();
+        let mut len = 0;
+        for item in items {
+            obj.set(&len.to_string(), item);
+            len += 1;
         }
+        obj.set(LEN_KEY, VarType::Int(len));
+        obj
+    }
+
+    fn members(&self) -> impl Iterator<Item = &(String, VarType)> {
+        self.entries.iter().flatten()
     }
 
     pub fn has(&self, name: &str) -> bool {
-        self.members.contains_key(name)
+        self.index.contains_key(name)
     }
 
     pub fn get(&self, name: &str) -> Option<VarType> {
-        self.members.get(name).cloned()
+        let (_, value) = self.entries[*self.index.get(name)?].as_ref()?;
+        Some(value.clone())
     }
 
     pub fn set(&mut self, name: &str, var: VarType) {
-        self.members.insert(name.to_string(), var);
+        match self.index.get(name) {
+            Some(&pos) => {
+                if let Some((_, value)) = &mut self.entries[pos] {
+                    *value = var;
+                }
+            }
+            None => {
+                self.index.insert(name.to_string(), self.entries.len());
+                self.entries.push(Some((name.to_string(), var)));
+            }
+        }
+    }
+
+    /// Removes the member `name` and returns its value, if it was there.
+    pub fn remove(&mut self, name: &str) -> Option<VarType> {
+        let pos = self.index.remove(name)?;
+        let (_, value) = self.entries[pos].take()?;
+        while let Some(None) = self.entries.last() {
+            self.entries.pop();
+        }
+        if self.entries.len() > 2 * self.index.len() {
+            self.entries.retain(Option::is_some);
+            for (pos, (key, _)) in self.entries.iter().flatten().enumerate() {
+                self.index.insert(key.clone(), pos);
+            }
+        }
+        Some(value)
+    }
+
+    /// Number of members, counting those set to `N0`.
+    pub fn count(&self) -> usize {
+        self.index.len()
     }
 
+    pub fn values(&self) -> impl Iterator<Item = &VarType> {
+        self.members().map(|(_, value)| value)
+    }
+
+    /// Removes every member and returns their values.
+    pub fn clear(&mut self) -> Vec<VarType> {
+        self.index.clear();
+        let entries = std::mem::take(&mut self.entries);
+        entries
+            .into_iter()
+            .flatten()
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Keys in the order they were first set.
     pub fn keys(&self) -> Vec<String> {
-        let mut keys: Vec<String> = self.members.keys().cloned().collect();
-        keys.sort();
-        keys
+        self.members().map(|(key, _)| key.clone()).collect()
+    }
+}
+
+impl fmt::Debug for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Object {{ members: ")?;
+        f.debug_map()
+            .entries(self.members().map(|(key, value)| (key, value)))
+            .finish()?;
+        write!(f, " }}")
     }
 }
 
@@ -128,4 +313,41 @@ impl Tuple {
     pub fn len(&self) -> usize {
         self.items.len()
     }
+
+    /// Item at `key`, an index or `len`, as with lists.
+    pub fn get(&self, key: &str) -> VarType {
+        if key == LEN_KEY {
+            return VarType::Int(self.len() as i64);
+        }
+        key.parse::<usize>()
+            .ok()
+            .and_then(|idx| self.items.get(idx).cloned())
+            .unwrap_or(VarType::Nzero)
+    }
+}
+
+/// Writes a tuple the way it is written in lIIl, as in `1 | 2 | "x"`. Nested
+/// tuples are put in parentheses, and tuples of fewer than two items in
+/// brackets.
+impl fmt::Display for Tuple {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bracket = self.len() < 2;
+        if bracket {
+            write!(f, "[")?;
+        }
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                write!(f, " | ")?;
+            }
+            match item {
+                VarType::String(s) => write!(f, "{}", string_literal(s))?,
+                VarType::Tuple(tuple) if tuple.len() >= 2 => write!(f, "({})", tuple)?,
+                item => write!(f, "{}", item.to_string())?,
+            }
+        }
+        if bracket {
+            write!(f, "]")?;
+        }
+        Ok(())
+    }
 }
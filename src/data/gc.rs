@@ -0,0 +1,171 @@
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    rc::{Rc, Weak},
+};
+
+use super::{
+    context::{Context, ContextRc},
+    data::{MemData, MemDataRc},
+    variable::VarType,
+};
+
+/// Counters of the cycle collector, for `stats@mmu`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GcStats {
+    pub collections: usize,
+    /// Objects, nodes and scopes found unreachable over all collections.
+    pub freed: usize,
+    /// Containers the collector knows of after the last collection.
+    pub tracked: usize,
+}
+
+/// A container that can be part of a reference cycle.
+enum Tracked {
+    Data(MemDataRc),
+    Context(ContextRc),
+}
+
+impl Tracked {
+    fn ptr(&self) -> *const () {
+        match self {
+            Tracked::Data(rc) => Rc::as_ptr(rc) as *const (),
+            Tracked::Context(rc) => Rc::as_ptr(rc) as *const (),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Tracked::Data(rc) => Rc::strong_count(rc),
+            Tracked::Context(rc) => Rc::strong_count(rc),
+        }
+    }
+
+    /// Pointers of the containers this one holds a strong reference to for
+    /// each such reference, or `None` if it is borrowed and cannot be looked
+    /// into.
+    fn children(&self) -> Option<Vec<*const ()>> {
+        let mut children = Vec::new();
+        match self {
+            Tracked::Data(rc) => match &*rc.try_borrow().ok()? {
+                MemData::Object(obj) => obj.values().for_each(|v| refs(v, &mut children)),
+                MemData::Mess(mess) => mess.values().for_each(|v| refs(v, &mut children)),
+                MemData::Node(node) => children.push(Rc::as_ptr(node.parent()) as *const ()),
+            },
+            Tracked::Context(rc) => {
+                let ctx = rc.try_borrow().ok()?;
+                children.push(Rc::as_ptr(&ctx.get_mess()) as *const ());
+                if let Some(parent) = ctx.get_parent() {
+                    children.push(Rc::as_ptr(&parent) as *const ());
+                }
+            }
+        }
+        Some(children)
+    }
+
+    /// Drops the references this holds to other values, which breaks every
+    /// cycle through it. The values are returned so that they are dropped
+    /// after all garbage has been cleared.
+    fn clear(&self) -> Vec<VarType> {
+        match self {
+            Tracked::Data(rc) => match rc.try_borrow_mut().as_deref_mut() {
+                Ok(MemData::Object(obj)) => obj.clear(),
+                Ok(MemData::Mess(mess)) => mess.clear(),
+                _ => Vec::new(),
+            },
+            Tracked::Context(_) => Vec::new(),
+        }
+    }
+}
+
+fn refs(value: &VarType, out: &mut Vec<*const ()>) {
+    match value {
+        VarType::Ref(rc) => out.push(Rc::as_ptr(rc) as *const ()),
+        VarType::Tuple(tuple) => tuple.items.iter().for_each(|v| refs(v, out)),
+        _ => (),
+    }
+}
+
+/// Frees the unreachable cycles among `objects`, `contexts` and the variables
+/// of `contexts`, and forgets the dead entries of both lists. Returns the
+/// number of containers freed.
+///
+/// A container is reachable if something other than the tracked containers
+/// holds a strong reference to it, e.g. a running node or a Rust local, or if
+/// a reachable container refers to it. Unreachable ones can only be kept
+/// alive by cycles, which are broken by clearing every unreachable object and
+/// scope.
+pub fn collect(
+    objects: &mut Vec<Weak<RefCell<MemData>>>,
+    contexts: &mut Vec<Weak<RefCell<Context>>>,
+) -> usize {
+    let mut tracked: Vec<Tracked> = Vec::new();
+    let mut index: HashMap<*const (), usize> = HashMap::new();
+    let mut track = |item: Tracked| {
+        if let Entry::Vacant(entry) = index.entry(item.ptr()) {
+            entry.insert(tracked.len());
+            tracked.push(item);
+        }
+    };
+    objects.retain(|weak| weak.strong_count() > 0);
+    contexts.retain(|weak| weak.strong_count() > 0);
+    for rc in objects.iter().filter_map(Weak::upgrade) {
+        track(Tracked::Data(rc));
+    }
+    for rc in contexts.iter().filter_map(Weak::upgrade) {
+        let mess = rc.try_borrow().map(|ctx| ctx.get_mess());
+        track(Tracked::Context(rc));
+        if let Ok(mess) = mess {
+            track(Tracked::Data(mess));
+        }
+    }
+    let mut seen = HashSet::new();
+    contexts.retain(|weak| seen.insert(weak.as_ptr()));
+
+    // References from outside the tracked containers, not counting the one
+    // held by `tracked` itself.
+    let mut external: Vec<usize> = tracked.iter().map(|t| t.strong_count() - 1).collect();
+    let mut edges: Vec<Vec<usize>> = Vec::with_capacity(tracked.len());
+    for item in &tracked {
+        let children = match item.children() {
+            Some(children) => children,
+            None => {
+                // Someone is using it right now.
+                external[edges.len()] = usize::MAX;
+                Vec::new()
+            }
+        };
+        let children: Vec<usize> = children
+            .iter()
+            .filter_map(|ptr| index.get(ptr).copied())
+            .collect();
+        for &child in &children {
+            external[child] = external[child].saturating_sub(1);
+        }
+        edges.push(children);
+    }
+
+    let mut reachable = vec![false; tracked.len()];
+    let mut pending: Vec<usize> = (0..tracked.len()).filter(|&i| external[i] > 0).collect();
+    while let Some(i) = pending.pop() {
+        if reachable[i] {
+            continue;
+        }
+        reachable[i] = true;
+        pending.extend(edges[i].iter().filter(|&&child| !reachable[child]));
+    }
+
+    let mut freed = 0;
+    let mut trash = Vec::new();
+    for (item, reachable) in tracked.iter().zip(reachable) {
+        if !reachable {
+            freed += 1;
+            trash.extend(item.clear());
+        }
+    }
+    drop(tracked);
+    drop(trash);
+    objects.retain(|weak| weak.strong_count() > 0);
+    contexts.retain(|weak| weak.strong_count() > 0);
+    freed
+}
@@ -3,11 +3,12 @@ use std::{
     rc::{Rc, Weak},
 };
 
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use super::{
-    context::{ContextRc, GlobalRc},
+    context::{Context, ContextRc, GlobalRc},
     data::{MemData, MemDataRc},
+    gc::{self, GcStats},
     module::{register_builtin_modules, ModuleFactoryManager},
     stack::ProgramStack,
 };
@@ -17,30 +18,126 @@ pub struct Global {
     pub builtin_modules: ModuleFactoryManager,
     pub stack: ProgramStack,
     pub objects: Vec<Weak<RefCell<MemData>>>,
+    /// Scopes captured by code nodes, which may take part in cycles.
+    contexts: Vec<Weak<RefCell<Context>>>,
+    /// Number of tracked containers at which to collect garbage next.
+    collect_at: usize,
+    pub gc_stats: GcStats,
+    /// Seed `rng` was created from, so that a run can be replayed.
+    pub seed: u64,
+    /// Source of every random decision: `maybe`, `!` and `mk x != y`.
+    pub rng: StdRng,
+    /// Buffers collecting program output instead of stdout, innermost last.
+    captures: Vec<String>,
 }
 
 impl Global {
+    pub const SEED_ENV: &'static str = "LIIL_SEED";
+    const MIN_COLLECT_AT: usize = 1024;
+
     pub fn new() -> Self {
         let mut builtin_modules = ModuleFactoryManager::new();
         register_builtin_modules(&mut builtin_modules);
+        let seed = Global::env_seed()
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| rand::thread_rng().gen());
         Global {
             context_root: None,
             builtin_modules,
             stack: ProgramStack::new(),
             objects: Vec::new(),
+            contexts: Vec::new(),
+            collect_at: Global::MIN_COLLECT_AT,
+            gc_stats: GcStats::default(),
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            captures: Vec::new(),
         }
     }
 
+    /// Seed given through the `LIIL_SEED` environment variable, if any, or
+    /// why it is not a valid seed.
+    pub fn env_seed() -> Result<Option<u64>, String> {
+        let value = match std::env::var(Global::SEED_ENV) {
+            Ok(value) => value,
+            Err(std::env::VarError::NotPresent) => return Ok(None),
+            Err(e) => return Err(format!("invalid value for '{}': {}", Global::SEED_ENV, e)),
+        };
+        value.trim().parse().map(Some).map_err(|e| {
+            format!(
+                "invalid value '{}' for '{}': {}",
+                value,
+                Global::SEED_ENV,
+                e
+            )
+        })
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
     pub fn new_rc() -> GlobalRc {
         Rc::new(RefCell::new(Global::new()))
     }
 
+    /// Prints program output, or appends it to the innermost capture.
+    pub fn write(&mut self, text: &str) {
+        match self.captures.last_mut() {
+            Some(capture) => capture.push_str(text),
+            None => print!("{}", text),
+        }
+    }
+
+    pub fn begin_capture(&mut self) {
+        self.captures.push(String::new());
+    }
+
+    /// Stops the innermost capture and returns what it collected.
+    pub fn end_capture(&mut self) -> String {
+        self.captures.pop().unwrap_or_default()
+    }
+
+    /// Tracks an object or a node for `!` and the garbage collector.
     pub fn register_object(&mut self, rc: &MemDataRc) {
-        self.objects.retain(|w| w.upgrade().is_some());
         self.objects.push(Rc::downgrade(rc));
+        self.maybe_collect();
+    }
+
+    /// Tracks a scope captured by a node for the garbage collector.
+    pub fn register_context(&mut self, ctx: &ContextRc) {
+        if let Some(last) = self.contexts.last() {
+            if std::ptr::eq(last.as_ptr(), Rc::as_ptr(ctx)) {
+                return;
+            }
+        }
+        self.contexts.push(Rc::downgrade(ctx));
+        self.maybe_collect();
+    }
+
+    fn maybe_collect(&mut self) {
+        // Collecting only when the tracked containers have doubled keeps
+        // registration O(1) amortized.
+        if self.objects.len() + self.contexts.len() >= self.collect_at {
+            self.collect();
+        }
+    }
+
+    /// Frees unreachable reference cycles and returns how many containers
+    /// were freed.
+    pub fn collect(&mut self) -> usize {
+        let freed = gc::collect(&mut self.objects, &mut self.contexts);
+        let tracked = self.objects.len() + self.contexts.len();
+        self.gc_stats.collections += 1;
+        self.gc_stats.freed += freed;
+        self.gc_stats.tracked = tracked;
+        self.collect_at = (tracked * 2).max(Global::MIN_COLLECT_AT);
+        freed
     }
 
-    pub fn random_other_object(&self, exclude: &MemDataRc) -> Option<MemDataRc> {
+    pub fn random_other_object(&mut self, exclude: &MemDataRc) -> Option<MemDataRc> {
         let candidates: Vec<MemDataRc> = self
             .objects
             .iter()
@@ -50,7 +147,7 @@ impl Global {
         if candidates.is_empty() {
             return None;
         }
-        let idx = rand::thread_rng().gen_range(0..candidates.len());
+        let idx = self.rng.gen_range(0..candidates.len());
         Some(candidates[idx].clone())
     }
 }
@@ -1,8 +1,6 @@
 use std::fmt::{self, Debug};
 
-use crate::{expr::MemberExpr, statement::CodeExecError};
-
-use super::{context::ContextRc, variable::VarType};
+use crate::expr::MemberExpr;
 
 #[derive(Clone)]
 pub enum LValue {
@@ -18,12 +16,3 @@ impl Debug for LValue {
         }
     }
 }
-
-impl LValue {
-    pub fn set(&self, ctx: &ContextRc, val: VarType) -> Result<(), CodeExecError> {
-        match self {
-            LValue::Identifier(id) => Ok(ctx.borrow().set_symbol(&id, val)),
-            LValue::MemberExpr(expr) => expr.set(ctx, val),
-        }
-    }
-}
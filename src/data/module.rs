@@ -1,21 +1,32 @@
 use std::{collections::HashMap, rc::Rc};
 
-use crate::module::{
-    ai::AiModule, cpu::CpuModule, test::TestModule, tp::TpModule, Module,
+use crate::{
+    module::{
+        ai::AiModule, alu::AluModule, cpu::CpuModule, fpga::FpgaModule, hdd::HddModule,
+        mmu::MmuModule, nic::NicModule, ram::RamModule, ssd::SsdModule, test::TestModule,
+        tp::TpModule, Module,
+    },
+    parser::parse_str,
+    statement::CodeExecError,
 };
 
 use super::context::ContextRc;
 
 pub type FactoryFn = Rc<dyn Fn(&ContextRc) -> Module>;
+pub type FallbackFn = Rc<dyn Fn(&ContextRc) -> Result<Module, CodeExecError>>;
 
 pub struct ModuleFactoryManager {
     factories: HashMap<String, FactoryFn>,
+    /// Looked up only after no module file is found, so that a file can
+    /// replace them.
+    fallbacks: HashMap<String, FallbackFn>,
 }
 
 impl ModuleFactoryManager {
     pub fn new() -> Self {
         Self {
             factories: HashMap::new(),
+            fallbacks: HashMap::new(),
         }
     }
 
@@ -26,11 +37,41 @@ impl ModuleFactoryManager {
     pub fn get_factory(&self, name: &str) -> Option<FactoryFn> {
         self.factories.get(name).map(|f| f.clone())
     }
+
+    pub fn add_fallback(&mut self, name: &str, factory: FallbackFn) {
+        self.fallbacks.insert(name.to_owned(), factory);
+    }
+
+    pub fn get_fallback(&self, name: &str) -> Option<FallbackFn> {
+        self.fallbacks.get(name).cloned()
+    }
+}
+
+/// Modules written in lIIl under `src/builtin`, compiled into the binary so
+/// that `rm` finds them wherever it runs. A module file of the same name next
+/// to the script or in the working directory takes precedence.
+pub(crate) const CODE_MODULES: &[(&str, &str)] = &[("ll", include_str!("../builtin/ll.lIIl"))];
+
+fn register_code_modules(manager: &mut ModuleFactoryManager) {
+    for &(name, source) in CODE_MODULES {
+        manager.add_fallback(
+            name,
+            Rc::new(move |parent: &ContextRc| parse_str(name, source, parent).map(Module::Code)),
+        );
+    }
 }
 
 pub fn register_builtin_modules(manager: &mut ModuleFactoryManager) {
     AiModule::register(manager);
+    AluModule::register(manager);
     CpuModule::register(manager);
+    FpgaModule::register(manager);
+    HddModule::register(manager);
+    MmuModule::register(manager);
+    NicModule::register(manager);
+    RamModule::register(manager);
+    SsdModule::register(manager);
     TestModule::register(manager);
     TpModule::register(manager);
+    register_code_modules(manager);
 }
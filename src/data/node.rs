@@ -1,10 +1,13 @@
-use std::fmt;
+use std::{fmt, rc::Rc};
 
-use crate::statement::{CodeExecError, Statement};
+use crate::{
+    statement::CodeExecError,
+    vm::{self, chunk::NodeProto},
+};
 
 use super::{
     context::{Context, ContextRc},
-    data::MemData,
+    data::{MemData, Object},
     variable::VarType,
 };
 
@@ -21,6 +24,30 @@ impl Node {
             Node::Native(node) => node.exec(args),
         }
     }
+
+    /// Name the node was defined with. Native nodes are anonymous.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Node::Code(node) => Some(&node.proto.name),
+            Node::Native(_) => None,
+        }
+    }
+
+    /// Context the node runs in, or whose scope a code node captured.
+    pub fn parent(&self) -> &ContextRc {
+        match self {
+            Node::Code(node) => &node.parent,
+            Node::Native(node) => &node.parent,
+        }
+    }
+
+    /// Path of the module the node was defined in.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Node::Code(node) => Some(&node.proto.path),
+            Node::Native(_) => None,
+        }
+    }
 }
 
 impl fmt::Debug for Node {
@@ -31,15 +58,14 @@ impl fmt::Debug for Node {
 
 pub struct CodeNode {
     parent: ContextRc,
-    pub args: Vec<String>,
-    pub body: Box<Statement>,
+    proto: Rc<NodeProto>,
 }
 
 impl fmt::Debug for CodeNode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("CodeNode")
-            .field("args", &self.args)
-            .field("body", &self.body)
+            .field("name", &self.proto.name)
+            .field("args", &self.proto.args)
             .finish()
     }
 }
@@ -48,43 +74,58 @@ impl Clone for CodeNode {
     fn clone(&self) -> Self {
         CodeNode {
             parent: self.parent.clone(),
-            args: self.args.clone(),
-            body: self.body.clone(),
+            proto: self.proto.clone(),
         }
     }
 }
 
 impl CodeNode {
-    pub fn new(parent: &ContextRc, body: Box<Statement>) -> Self {
+    pub fn new(parent: &ContextRc, proto: &Rc<NodeProto>) -> Self {
         CodeNode {
             parent: parent.clone(),
-            args: Vec::new(),
-            body: body,
+            proto: proto.clone(),
         }
     }
 
     pub fn exec(&mut self, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
-        let ctx = Context::new_rc(&self.parent);
-        if args.len() > self.args.len() {
+        let proto = &self.proto;
+        let layout = &proto.chunk.layout;
+        let ctx = Context::new_rc_with_layout(&self.parent, layout);
+        let named = proto.args.len();
+        if args.len() < proto.required || (proto.rest_slot.is_none() && args.len() > named) {
             return Err(CodeExecError::new(
                 &ctx.borrow(),
                 format!(
-                    "Too many arguments for function: expected {}, got {}",
-                    self.args.len(),
+                    "Node {} takes {}, got {}",
+                    proto.name,
+                    proto.arity(),
                     args.len()
                 ),
             ));
         }
-        for (value, name) in args.iter().zip(&self.args) {
-            ctx.borrow_mut().set_symbol(&name, value.clone());
+        for (value, slot) in args.iter().zip(&proto.arg_slots) {
+            let slot = *slot as usize;
+            ctx.borrow()
+                .set_slot(slot, layout.name(slot), value.clone());
         }
-        Context::with(&ctx, || {
-            self.body.exec(&ctx).map(|v| v.unwrap_or(VarType::Nzero))
-        })
+        if let Some(slot) = proto.rest_slot {
+            let rest = Object::list(args.iter().skip(named).cloned());
+            let rc = MemData::new_rc(MemData::Object(rest));
+            ctx.borrow().get_global().borrow_mut().register_object(&rc);
+            let slot = slot as usize;
+            ctx.borrow()
+                .set_slot(slot, layout.name(slot), VarType::Ref(rc));
+        }
+        Context::with(&ctx, || vm::run(&proto.chunk, &ctx))
     }
 }
 
-pub type NativeFunc = fn(parent: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError>;
+/// Body of a native node. It gets the context the node was created in.
+pub type NativeFunc = Rc<dyn Fn(&ContextRc, &Vec<VarType>) -> Result<VarType, CodeExecError>>;
+
+/// A plain function usable as a `NativeFunc`, as modules define their
+/// members.
+pub type NativeFnPtr = fn(&ContextRc, &Vec<VarType>) -> Result<VarType, CodeExecError>;
 
 #[derive(Clone)]
 pub struct NativeNode {
@@ -104,7 +145,14 @@ impl NativeNode {
         (self.func)(&self.parent, args)
     }
 
-    pub fn as_vartype(parent: &ContextRc, func: NativeFunc) -> VarType {
+    pub fn as_vartype(
+        parent: &ContextRc,
+        func: impl Fn(&ContextRc, &Vec<VarType>) -> Result<VarType, CodeExecError> + 'static,
+    ) -> VarType {
+        NativeNode::func_as_vartype(parent, Rc::new(func))
+    }
+
+    pub fn func_as_vartype(parent: &ContextRc, func: NativeFunc) -> VarType {
         let node = MemData::Node(Node::Native(NativeNode::new(parent, func)));
         VarType::Ref(MemData::new_rc(node))
     }
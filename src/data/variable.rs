@@ -1,4 +1,12 @@
-use std::fmt::{self, Debug};
+use std::{
+    fmt::{self, Debug},
+    rc::Rc,
+};
+
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::statement::CodeExecError;
 
 use super::data::{MemDataRc, Tuple};
 
@@ -8,6 +16,9 @@ pub enum VarType {
     Nzero,
     Bool(bool),
     Int(i64),
+    /// An integer that does not fit in an `Int`. Integers that fit are always
+    /// `Int`s, so arithmetic switches between the two as needed.
+    BigInt(Rc<BigInt>),
     Float(f64),
     String(String),
     Tuple(Tuple),
@@ -20,6 +31,7 @@ impl Debug for VarType {
             VarType::Nzero => write!(f, "N0"),
             VarType::Bool(val) => write!(f, "Bool({})", val),
             VarType::Int(val) => write!(f, "Int({})", val),
+            VarType::BigInt(val) => write!(f, "BigInt({})", val),
             VarType::Float(val) => write!(f, "Float({})", val),
             VarType::String(val) => write!(f, "String({})", val),
             VarType::Tuple(val) => write!(f, "Tuple({:?})", val),
@@ -34,9 +46,10 @@ impl ToString for VarType {
             VarType::Nzero => "N0".to_owned(),
             VarType::Bool(val) => format!("{}", val),
             VarType::Int(val) => format!("{}", val),
+            VarType::BigInt(val) => format!("{}", val),
             VarType::Float(val) => format!("{}", val),
             VarType::String(val) => format!("{}", val),
-            VarType::Tuple(val) => format!("{:?}", val),
+            VarType::Tuple(val) => val.to_string(),
             VarType::Ref(val) => format!("{:?}", val.borrow()),
         }
     }
@@ -48,6 +61,7 @@ impl From<&VarType> for bool {
             VarType::Nzero => false,
             VarType::Bool(val) => *val,
             VarType::Int(val) => *val != 0,
+            VarType::BigInt(_val) => true,
             VarType::Float(val) => *val != 0.0,
             VarType::String(val) => val != "",
             VarType::Tuple(val) => val.len() != 0,
@@ -61,3 +75,90 @@ impl From<VarType> for bool {
         (&value).into()
     }
 }
+
+impl From<i64> for VarType {
+    fn from(value: i64) -> Self {
+        VarType::Int(value)
+    }
+}
+
+/// An `Int` if the value fits in one.
+impl From<BigInt> for VarType {
+    fn from(value: BigInt) -> Self {
+        match value.to_i64() {
+            Some(value) => VarType::Int(value),
+            None => VarType::BigInt(Rc::new(value)),
+        }
+    }
+}
+
+impl From<f64> for VarType {
+    fn from(value: f64) -> Self {
+        VarType::Float(value)
+    }
+}
+
+impl From<bool> for VarType {
+    fn from(value: bool) -> Self {
+        VarType::Bool(value)
+    }
+}
+
+impl From<String> for VarType {
+    fn from(value: String) -> Self {
+        VarType::String(value)
+    }
+}
+
+impl From<&str> for VarType {
+    fn from(value: &str) -> Self {
+        VarType::String(value.to_owned())
+    }
+}
+
+/// `None` is `N0`.
+impl<T: Into<VarType>> From<Option<T>> for VarType {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(VarType::Nzero, Into::into)
+    }
+}
+
+fn expected(what: &str, value: &VarType) -> CodeExecError {
+    CodeExecError::new_str(format!("Expected {}, got {:?}", what, value))
+}
+
+impl TryFrom<VarType> for i64 {
+    type Error = CodeExecError;
+
+    fn try_from(value: VarType) -> Result<Self, Self::Error> {
+        match value {
+            VarType::Int(value) => Ok(value),
+            value => Err(expected("Int", &value)),
+        }
+    }
+}
+
+/// Integers are converted too.
+impl TryFrom<VarType> for f64 {
+    type Error = CodeExecError;
+
+    fn try_from(value: VarType) -> Result<Self, Self::Error> {
+        match value {
+            VarType::Int(value) => Ok(value as f64),
+            VarType::BigInt(value) => Ok(value.to_f64().unwrap_or(f64::NAN)),
+            VarType::Float(value) => Ok(value),
+            value => Err(expected("Float", &value)),
+        }
+    }
+}
+
+impl TryFrom<VarType> for String {
+    type Error = CodeExecError;
+
+    fn try_from(value: VarType) -> Result<Self, Self::Error> {
+        match value {
+            VarType::String(value) => Ok(value),
+            value => Err(expected("String", &value)),
+        }
+    }
+}
@@ -1,15 +1,18 @@
-use std::{fmt::Debug, mem, rc::Rc};
+use std::{
+    fmt::{Debug, Display},
+    mem,
+    rc::Rc,
+};
 
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
 use rand::Rng;
 
 use crate::{
     data::context::Context,
-    data::{
-        context::ContextRc,
-        data::{MemData, MemDataRc, Object, Tuple},
-        variable::VarType,
-    },
-    statement::CodeExecError,
+    data::{context::ContextRc, data::MemData, variable::VarType},
+    statement::{node_def::NodeDefStatement, CodeExecError, TraceFrame},
+    utils::span::Span,
 };
 
 fn expr_type_error_2(ctx: &Context, lhs: VarType, rhs: VarType) -> CodeExecError {
@@ -20,8 +23,26 @@ fn expr_type_error_1(ctx: &Context, value: VarType) -> CodeExecError {
     CodeExecError::new(ctx, format!("Type error: {:?}", value))
 }
 
+fn by_zero(ctx: &Context, what: &str, op: &str, lhs: impl Display) -> CodeExecError {
+    CodeExecError::new(ctx, format!("{} by zero: {} {} 0", what, lhs, op))
+}
+
+/// Applies `checked` to two `Int`s, or `big` if the result does not fit in an
+/// `Int`.
+fn int_op(
+    l: i64,
+    r: i64,
+    checked: fn(i64, i64) -> Option<i64>,
+    big: fn(BigInt, BigInt) -> BigInt,
+) -> VarType {
+    match checked(l, r) {
+        Some(value) => VarType::Int(value),
+        None => VarType::from(big(BigInt::from(l), BigInt::from(r))),
+    }
+}
+
 fn promote_add(
-    ctx: &Context,
+    ctx: &ContextRc,
     lhs: VarType,
     rhs: VarType,
 ) -> Result<(VarType, VarType), CodeExecError> {
@@ -35,7 +56,7 @@ fn promote_add(
 }
 
 fn promote_type(
-    ctx: &Context,
+    ctx: &ContextRc,
     lhs: VarType,
     rhs: VarType,
 ) -> Result<(VarType, VarType), CodeExecError> {
@@ -46,12 +67,30 @@ fn promote_type(
         (VarType::Nzero, _) | (_, VarType::Nzero) => Ok((lhs, rhs)),
         (VarType::Int(l), VarType::Float(_r)) => Ok((VarType::Float(*l as f64), rhs)),
         (VarType::Float(_l), VarType::Int(r)) => Ok((lhs, VarType::Float(*r as f64))),
-        _ => Err(expr_type_error_2(ctx, lhs, rhs)),
+        (VarType::Int(l), VarType::BigInt(_r)) => {
+            Ok((VarType::BigInt(Rc::new(BigInt::from(*l))), rhs))
+        }
+        (VarType::BigInt(_l), VarType::Int(r)) => {
+            Ok((lhs, VarType::BigInt(Rc::new(BigInt::from(*r)))))
+        }
+        (VarType::BigInt(l), VarType::Float(_r)) => {
+            Ok((VarType::Float(l.to_f64().unwrap_or(f64::NAN)), rhs))
+        }
+        (VarType::Float(_l), VarType::BigInt(r)) => {
+            Ok((lhs, VarType::Float(r.to_f64().unwrap_or(f64::NAN))))
+        }
+        _ => Err(expr_type_error_2(&ctx.borrow(), lhs, rhs)),
     }
 }
 
 #[derive(Clone)]
-pub enum Expr {
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Clone)]
+pub enum ExprKind {
     Literal(LiteralExpr),
     Lol,
     Identifier(IdentifierExpr),
@@ -67,57 +106,40 @@ pub enum Expr {
     Bracket(TupleExpr),
     Member(MemberExpr),
     NodeCall(NodeCallExpr),
+    /// An anonymous node, `(args << body)`.
+    Node(NodeDefStatement),
 }
 
 impl Debug for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Expr::Literal(_expr) => write!(f, "LiteralExpr"),
-            Expr::Lol => write!(f, "LolExpr"),
-            Expr::Identifier(_expr) => write!(f, "IdentifierExpr"),
-            Expr::Add(_expr) => write!(f, "AddExpr"),
-            Expr::Sub(_expr) => write!(f, "SubExpr"),
-            Expr::Mul(_expr) => write!(f, "MulExpr"),
-            Expr::Div(_expr) => write!(f, "DivExpr"),
-            Expr::Mod(_expr) => write!(f, "ModExpr"),
-            Expr::Cmp(_expr) => write!(f, "CmpExpr"),
-            Expr::Not(_expr) => write!(f, "NotExpr"),
-            Expr::Neg(_expr) => write!(f, "NegExpr"),
-            Expr::Tuple(_expr) => write!(f, "TupleExpr"),
-            Expr::Bracket(_expr) => write!(f, "BracketExpr"),
-            Expr::Member(_expr) => write!(f, "MemberExpr"),
-            Expr::NodeCall(_expr) => write!(f, "NodeCallExpr"),
+        match &self.kind {
+            ExprKind::Literal(_expr) => write!(f, "LiteralExpr"),
+            ExprKind::Lol => write!(f, "LolExpr"),
+            ExprKind::Identifier(_expr) => write!(f, "IdentifierExpr"),
+            ExprKind::Add(_expr) => write!(f, "AddExpr"),
+            ExprKind::Sub(_expr) => write!(f, "SubExpr"),
+            ExprKind::Mul(_expr) => write!(f, "MulExpr"),
+            ExprKind::Div(_expr) => write!(f, "DivExpr"),
+            ExprKind::Mod(_expr) => write!(f, "ModExpr"),
+            ExprKind::Cmp(_expr) => write!(f, "CmpExpr"),
+            ExprKind::Not(_expr) => write!(f, "NotExpr"),
+            ExprKind::Neg(_expr) => write!(f, "NegExpr"),
+            ExprKind::Tuple(_expr) => write!(f, "TupleExpr"),
+            ExprKind::Bracket(_expr) => write!(f, "BracketExpr"),
+            ExprKind::Member(_expr) => write!(f, "MemberExpr"),
+            ExprKind::NodeCall(_expr) => write!(f, "NodeCallExpr"),
+            ExprKind::Node(_expr) => write!(f, "NodeExpr"),
         }
     }
 }
 
 impl Expr {
-    pub fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        match self {
-            Expr::Literal(expr) => expr.eval(ctx),
-            Expr::Lol => {
-                let rc = MemData::new_rc(MemData::Object(Object::new()));
-                ctx.borrow().get_global().borrow_mut().register_object(&rc);
-                Ok(VarType::Ref(rc))
-            }
-            Expr::Identifier(expr) => expr.eval(ctx),
-            Expr::Add(expr) => expr.eval(ctx),
-            Expr::Sub(expr) => expr.eval(ctx),
-            Expr::Mul(expr) => expr.eval(ctx),
-            Expr::Div(expr) => expr.eval(ctx),
-            Expr::Mod(expr) => expr.eval(ctx),
-            Expr::Cmp(expr) => expr.eval(ctx),
-            Expr::Not(expr) => expr.eval(ctx),
-            Expr::Neg(expr) => expr.eval(ctx),
-            Expr::Tuple(expr) => expr.eval(ctx),
-            Expr::Bracket(expr) => expr.eval(ctx),
-            Expr::Member(expr) => expr.eval(ctx),
-            Expr::NodeCall(expr) => expr.eval(ctx),
-        }
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Expr { kind, span }
     }
 
-    pub fn literal(value: VarType) -> Expr {
-        Expr::Literal(LiteralExpr { value })
+    pub fn literal(value: VarType, span: Span) -> Expr {
+        Expr::new(ExprKind::Literal(LiteralExpr { value }), span)
     }
 }
 
@@ -126,23 +148,11 @@ pub struct LiteralExpr {
     pub value: VarType,
 }
 
-impl LiteralExpr {
-    fn eval(&self, _: &ContextRc) -> Result<VarType, CodeExecError> {
-        Ok(self.value.clone())
-    }
-}
-
 #[derive(Clone)]
 pub struct IdentifierExpr {
     pub name: String,
 }
 
-impl IdentifierExpr {
-    fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        ctx.borrow().get_symbol_or_err(&self.name)
-    }
-}
-
 #[derive(Clone)]
 pub struct AddExpr {
     pub lhs: Box<Expr>,
@@ -150,14 +160,17 @@ pub struct AddExpr {
 }
 
 impl AddExpr {
-    fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        let vl = self.lhs.eval(ctx)?;
-        let vr = self.rhs.eval(ctx)?;
-        let (lhs, rhs) = promote_add(&ctx.borrow(), vl, vr)?;
+    pub fn apply(ctx: &ContextRc, vl: VarType, vr: VarType) -> Result<VarType, CodeExecError> {
+        let (lhs, rhs) = promote_add(ctx, vl, vr)?;
         match (lhs, rhs) {
-            (VarType::Int(l), VarType::Int(r)) => Ok(VarType::Int(l + r)),
+            (VarType::Int(l), VarType::Int(r)) => Ok(int_op(l, r, i64::checked_add, |l, r| l + r)),
+            (VarType::BigInt(l), VarType::BigInt(r)) => Ok(VarType::from(&*l + &*r)),
             (VarType::Float(l), VarType::Float(r)) => Ok(VarType::Float(l + r)),
             (VarType::String(l), VarType::String(r)) => Ok(VarType::String(l + &r)),
+            (VarType::Tuple(mut l), VarType::Tuple(r)) => {
+                l.items.extend(r.items);
+                Ok(VarType::Tuple(l))
+            }
             (l, r) => Err(expr_type_error_2(&ctx.borrow(), l, r)),
         }
     }
@@ -170,12 +183,11 @@ pub struct SubExpr {
 }
 
 impl SubExpr {
-    fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        let vl = self.lhs.eval(ctx)?;
-        let vr = self.rhs.eval(ctx)?;
-        let (lhs, rhs) = promote_type(&ctx.borrow(), vl, vr)?;
+    pub fn apply(ctx: &ContextRc, vl: VarType, vr: VarType) -> Result<VarType, CodeExecError> {
+        let (lhs, rhs) = promote_type(ctx, vl, vr)?;
         match (lhs, rhs) {
-            (VarType::Int(l), VarType::Int(r)) => Ok(VarType::Int(l - r)),
+            (VarType::Int(l), VarType::Int(r)) => Ok(int_op(l, r, i64::checked_sub, |l, r| l - r)),
+            (VarType::BigInt(l), VarType::BigInt(r)) => Ok(VarType::from(&*l - &*r)),
             (VarType::Float(l), VarType::Float(r)) => Ok(VarType::Float(l - r)),
             (l, r) => Err(expr_type_error_2(&ctx.borrow(), l, r)),
         }
@@ -189,12 +201,11 @@ pub struct MulExpr {
 }
 
 impl MulExpr {
-    fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        let vl = self.lhs.eval(ctx)?;
-        let vr = self.rhs.eval(ctx)?;
-        let (lhs, rhs) = promote_type(&ctx.borrow(), vl, vr)?;
+    pub fn apply(ctx: &ContextRc, vl: VarType, vr: VarType) -> Result<VarType, CodeExecError> {
+        let (lhs, rhs) = promote_type(ctx, vl, vr)?;
         match (lhs, rhs) {
-            (VarType::Int(l), VarType::Int(r)) => Ok(VarType::Int(l * r)),
+            (VarType::Int(l), VarType::Int(r)) => Ok(int_op(l, r, i64::checked_mul, |l, r| l * r)),
+            (VarType::BigInt(l), VarType::BigInt(r)) => Ok(VarType::from(&*l * &*r)),
             (VarType::Float(l), VarType::Float(r)) => Ok(VarType::Float(l * r)),
             (l, r) => Err(expr_type_error_2(&ctx.borrow(), l, r)),
         }
@@ -208,12 +219,15 @@ pub struct DivExpr {
 }
 
 impl DivExpr {
-    fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        let vl = self.lhs.eval(ctx)?;
-        let vr = self.rhs.eval(ctx)?;
-        let (lhs, rhs) = promote_type(&ctx.borrow(), vl, vr)?;
+    pub fn apply(ctx: &ContextRc, vl: VarType, vr: VarType) -> Result<VarType, CodeExecError> {
+        let (lhs, rhs) = promote_type(ctx, vl, vr)?;
         match (lhs, rhs) {
-            (VarType::Int(l), VarType::Int(r)) => Ok(VarType::Int(l / r)),
+            (VarType::Int(l), VarType::Int(0)) => Err(by_zero(&ctx.borrow(), "Division", "/", l)),
+            (VarType::BigInt(l), VarType::BigInt(r)) if r.is_zero() => {
+                Err(by_zero(&ctx.borrow(), "Division", "/", l))
+            }
+            (VarType::Int(l), VarType::Int(r)) => Ok(int_op(l, r, i64::checked_div, |l, r| l / r)),
+            (VarType::BigInt(l), VarType::BigInt(r)) => Ok(VarType::from(&*l / &*r)),
             (VarType::Float(l), VarType::Float(r)) => Ok(VarType::Float(l / r)),
             (l, r) => Err(expr_type_error_2(&ctx.borrow(), l, r)),
         }
@@ -227,19 +241,22 @@ pub struct ModExpr {
 }
 
 impl ModExpr {
-    fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        let vl = self.lhs.eval(ctx)?;
-        let vr = self.rhs.eval(ctx)?;
-        let (lhs, rhs) = promote_type(&ctx.borrow(), vl, vr)?;
+    pub fn apply(ctx: &ContextRc, vl: VarType, vr: VarType) -> Result<VarType, CodeExecError> {
+        let (lhs, rhs) = promote_type(ctx, vl, vr)?;
         match (lhs, rhs) {
-            (VarType::Int(l), VarType::Int(r)) => Ok(VarType::Int(l % r)),
+            (VarType::Int(l), VarType::Int(0)) => Err(by_zero(&ctx.borrow(), "Modulo", "%", l)),
+            (VarType::BigInt(l), VarType::BigInt(r)) if r.is_zero() => {
+                Err(by_zero(&ctx.borrow(), "Modulo", "%", l))
+            }
+            (VarType::Int(l), VarType::Int(r)) => Ok(int_op(l, r, i64::checked_rem, |l, r| l % r)),
+            (VarType::BigInt(l), VarType::BigInt(r)) => Ok(VarType::from(&*l % &*r)),
             (VarType::Float(l), VarType::Float(r)) => Ok(VarType::Float(l % r)),
             (l, r) => Err(expr_type_error_2(&ctx.borrow(), l, r)),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum CompareOp {
     Less,
     Greater,
@@ -257,11 +274,11 @@ pub struct CompareExpr {
 }
 
 impl CompareExpr {
-    fn compare<T>(&self, lhs: T, rhs: T) -> Result<VarType, CodeExecError>
+    fn compare<T>(op: CompareOp, lhs: T, rhs: T) -> Result<VarType, CodeExecError>
     where
         T: PartialOrd + Debug,
     {
-        match self.op {
+        match op {
             CompareOp::Less => Ok(VarType::Bool(lhs < rhs)),
             CompareOp::Greater => Ok(VarType::Bool(lhs > rhs)),
             CompareOp::Equal => Ok(VarType::Bool(lhs == rhs)),
@@ -271,27 +288,31 @@ impl CompareExpr {
         }
     }
 
-    fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        let vl = self.lhs.eval(ctx)?;
-        let vr = self.rhs.eval(ctx)?;
-        let (lhs, rhs) = promote_type(&ctx.borrow(), vl, vr)?;
+    pub fn apply(
+        ctx: &ContextRc,
+        op: CompareOp,
+        vl: VarType,
+        vr: VarType,
+    ) -> Result<VarType, CodeExecError> {
+        let (lhs, rhs) = promote_type(ctx, vl, vr)?;
         match (lhs, rhs) {
-            (VarType::Nzero, VarType::Nzero) => match self.op {
+            (VarType::Nzero, VarType::Nzero) => match op {
                 CompareOp::Equal | CompareOp::LessEqual | CompareOp::GreaterEqual => {
                     Ok(VarType::Bool(true))
                 }
                 _ => Ok(VarType::Bool(false)),
             },
-            (VarType::Nzero, r) | (r, VarType::Nzero) => match self.op {
+            (VarType::Nzero, r) | (r, VarType::Nzero) => match op {
                 CompareOp::Equal => Ok(VarType::Bool(false)),
                 CompareOp::NotEqual => Ok(VarType::Bool(true)),
                 _ => Err(expr_type_error_2(&ctx.borrow(), VarType::Nzero, r)),
             },
-            (VarType::Bool(l), VarType::Bool(r)) => self.compare(l, r),
-            (VarType::Int(l), VarType::Int(r)) => self.compare(l, r),
-            (VarType::Float(l), VarType::Float(r)) => self.compare(l, r),
-            (VarType::String(l), VarType::String(r)) => self.compare(l, r),
-            (VarType::Ref(l), VarType::Ref(r)) => match self.op {
+            (VarType::Bool(l), VarType::Bool(r)) => Self::compare(op, l, r),
+            (VarType::Int(l), VarType::Int(r)) => Self::compare(op, l, r),
+            (VarType::BigInt(l), VarType::BigInt(r)) => Self::compare(op, l, r),
+            (VarType::Float(l), VarType::Float(r)) => Self::compare(op, l, r),
+            (VarType::String(l), VarType::String(r)) => Self::compare(op, l, r),
+            (VarType::Ref(l), VarType::Ref(r)) => match op {
                 CompareOp::Equal => Ok(VarType::Bool(Rc::ptr_eq(&l, &r))),
                 CompareOp::NotEqual => Ok(VarType::Bool(!Rc::ptr_eq(&l, &r))),
                 _ => Err(expr_type_error_2(
@@ -311,10 +332,13 @@ pub struct NegExpr {
 }
 
 impl NegExpr {
-    fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        let value = self.value.eval(ctx)?;
+    pub fn apply(ctx: &ContextRc, value: VarType) -> Result<VarType, CodeExecError> {
         match value {
-            VarType::Int(value) => Ok(VarType::Int(-value)),
+            VarType::Int(value) => Ok(match value.checked_neg() {
+                Some(value) => VarType::Int(value),
+                None => VarType::from(-BigInt::from(value)),
+            }),
+            VarType::BigInt(value) => Ok(VarType::from(-&*value)),
             VarType::Float(value) => Ok(VarType::Float(-value)),
             _ => Err(expr_type_error_1(&ctx.borrow(), value)),
         }
@@ -327,8 +351,7 @@ pub struct NotExpr {
 }
 
 impl NotExpr {
-    fn sample_int(value: i64) -> i64 {
-        let mut rng = rand::thread_rng();
+    fn sample_int(rng: &mut impl Rng, value: i64) -> i64 {
         loop {
             let sample = rng.gen_range(i64::MIN..=i64::MAX);
             if sample != value {
@@ -336,8 +359,7 @@ impl NotExpr {
             }
         }
     }
-    fn sample_float(value: f64) -> f64 {
-        let mut rng = rand::thread_rng();
+    fn sample_float(rng: &mut impl Rng, value: f64) -> f64 {
         loop {
             let sample = rng.gen_range(0.0..1.0);
             if sample != value {
@@ -345,18 +367,21 @@ impl NotExpr {
             }
         }
     }
-    fn sample_ref() -> i64 {
-        Self::sample_int(0)
+    fn sample_ref(rng: &mut impl Rng) -> i64 {
+        Self::sample_int(rng, 0)
     }
     pub fn not(ctx: &ContextRc, value: VarType) -> Result<VarType, CodeExecError> {
+        let global = ctx.borrow().get_global();
+        let mut global = global.borrow_mut();
         match value {
-            VarType::Int(value) => Ok(VarType::Int(Self::sample_int(value))),
-            VarType::Float(value) => Ok(VarType::Float(Self::sample_float(value))),
+            VarType::Int(value) => Ok(VarType::Int(Self::sample_int(&mut global.rng, value))),
+            VarType::BigInt(_value) => Ok(VarType::Int(global.rng.gen())),
+            VarType::Float(value) => Ok(VarType::Float(Self::sample_float(&mut global.rng, value))),
             VarType::String(value) => Ok(VarType::String("!".to_owned() + &value)),
             VarType::Bool(value) => Ok(VarType::Bool(!value)),
             VarType::Ref(rc) => {
                 if matches!(*rc.borrow(), MemData::Object(_)) {
-                    match ctx.borrow().get_global().borrow().random_other_object(&rc) {
+                    match global.random_other_object(&rc) {
                         Some(other) => Ok(VarType::Ref(other)),
                         None => Ok(VarType::Nzero),
                     }
@@ -364,13 +389,10 @@ impl NotExpr {
                     Ok(VarType::Nzero)
                 }
             }
-            VarType::Nzero => Ok(VarType::Int(Self::sample_ref())),
+            VarType::Nzero => Ok(VarType::Int(Self::sample_ref(&mut global.rng))),
             _ => Err(expr_type_error_1(&ctx.borrow(), value)),
         }
     }
-    fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        Self::not(ctx, self.value.eval(ctx)?)
-    }
 }
 
 #[derive(Clone)]
@@ -378,16 +400,6 @@ pub struct TupleExpr {
     pub values: Vec<Expr>,
 }
 
-impl TupleExpr {
-    fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        let mut items = Vec::new();
-        for value in &self.values {
-            items.push(value.eval(ctx)?);
-        }
-        Ok(VarType::Tuple(Tuple { items }))
-    }
-}
-
 #[derive(Clone)]
 pub struct MemberExpr {
     pub lhs: Box<Expr>,
@@ -395,42 +407,15 @@ pub struct MemberExpr {
 }
 
 impl MemberExpr {
-    fn get_data(&self, ctx: &ContextRc) -> Result<MemDataRc, CodeExecError> {
-        let parent = self.rhs.eval(ctx)?;
-        if let VarType::Ref(data) = parent {
-            Ok(data)
-        } else {
-            Err(CodeExecError::new(
-                &ctx.borrow(),
-                format!("Expected ref, got {:?}", parent),
-            ))
-        }
-    }
-    fn get_key(&self, ctx: &ContextRc) -> Result<String, CodeExecError> {
-        if let Expr::Identifier(id) = &*self.lhs {
-            Ok(id.name.clone())
-        } else if let Expr::Bracket(bracket) = &*self.lhs {
-            let arr = bracket.eval(ctx)?;
-            if let VarType::Tuple(tuple) = arr {
-                if tuple.len() == 1 {
-                    Ok(tuple.items[0].to_string())
-                } else {
-                    Err(CodeExecError::new(
-                        &ctx.borrow(),
-                        format!("Cannot index with {:?}", tuple),
-                    ))
-                }
-            } else {
-                panic!("Expected tuple, got {:?}", arr)
-            }
-        } else {
-            Ok(self.lhs.eval(ctx)?.to_string())
+    pub fn key_string(key: VarType) -> String {
+        match key {
+            VarType::String(key) => key,
+            key => key.to_string(),
         }
     }
-    fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        let key = self.get_key(ctx)?;
-        let parent = self.rhs.eval(ctx)?;
-        
+    pub fn get(ctx: &ContextRc, key: VarType, parent: VarType) -> Result<VarType, CodeExecError> {
+        let key = Self::key_string(key);
+
         if let VarType::String(s) = parent {
             if let Ok(idx) = key.parse::<usize>() {
                 if let Some(c) = s.chars().nth(idx) {
@@ -439,23 +424,38 @@ impl MemberExpr {
             }
             return Ok(VarType::Nzero);
         }
-        
+
+        if let VarType::Tuple(tuple) = parent {
+            return Ok(tuple.get(&key));
+        }
+
         if let VarType::Ref(data) = parent {
             let borrowed_data = data.borrow();
             return Ok(borrowed_data.get(&key));
         }
-        
+
         Err(CodeExecError::new(
             &ctx.borrow(),
-            format!("Expected ref or string, got {:?}", parent),
+            format!("Expected ref, string or tuple, got {:?}", parent),
         ))
     }
 
-    pub fn set(&self, ctx: &ContextRc, val: VarType) -> Result<(), CodeExecError> {
-        let key = self.get_key(ctx)?;
-        let data = self.get_data(ctx)?;
-        let mut borrowed_data = data.borrow_mut();
-        borrowed_data.set(&ctx.borrow(), &key, val)
+    pub fn set(
+        ctx: &ContextRc,
+        key: VarType,
+        parent: VarType,
+        val: VarType,
+    ) -> Result<(), CodeExecError> {
+        let key = Self::key_string(key);
+        if let VarType::Ref(data) = parent {
+            let mut borrowed_data = data.borrow_mut();
+            borrowed_data.set(&ctx.borrow(), &key, val)
+        } else {
+            Err(CodeExecError::new(
+                &ctx.borrow(),
+                format!("Expected ref, got {:?}", parent),
+            ))
+        }
     }
 }
 
@@ -466,9 +466,16 @@ pub struct NodeCallExpr {
 }
 
 impl NodeCallExpr {
-    fn eval(&self, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
-        let node = self.node.eval(ctx)?;
-        let args = self.args.eval(ctx)?;
+    /// Calls `node`, recording the call in the traceback of any error.
+    /// `callee` is the source of the node expression, used to name native
+    /// nodes.
+    pub fn call(
+        ctx: &ContextRc,
+        node: VarType,
+        args: &Vec<VarType>,
+        call_site: &Span,
+        callee: &Span,
+    ) -> Result<VarType, CodeExecError> {
         if let VarType::Ref(data) = node {
             let mut node_copy;
             if let MemData::Node(node) = &*data.borrow() {
@@ -479,11 +486,13 @@ impl NodeCallExpr {
                     format!("Expected node, got {:?}", data),
                 ));
             }
-            if let VarType::Tuple(args_tuple) = args {
-                node_copy.exec(&args_tuple.items)
-            } else {
-                node_copy.exec(&vec![args])
-            }
+            node_copy.exec(args).map_err(|e| {
+                e.called_from(TraceFrame {
+                    name: node_copy.name().unwrap_or(callee.as_str()).to_owned(),
+                    path: node_copy.path().map(|p| p.to_owned()),
+                    call_site: call_site.clone(),
+                })
+            })
         } else {
             Err(CodeExecError::new(
                 &ctx.borrow(),
@@ -0,0 +1,264 @@
+use std::{fs, rc::Rc};
+
+use crate::{
+    data::{
+        context::{Context, ContextRc, GlobalRc},
+        data::{MemData, Mess, Object, LEN_KEY},
+        node::{NativeFunc, NativeNode},
+        variable::VarType,
+    },
+    module::{CodeModule, IModule, Module, NativeModule},
+    parser::parse,
+    statement::{CodeExecError, Statements},
+    utils::path::Path,
+    vm::{self, compiler::Compiler},
+};
+
+/// A native module defined by the host application, loaded with `rm` like
+/// the builtin ones.
+#[derive(Clone, Default)]
+pub struct HostModule {
+    members: Vec<(String, HostMember)>,
+}
+
+#[derive(Clone)]
+enum HostMember {
+    Value(VarType),
+    Node(NativeFunc),
+}
+
+impl HostModule {
+    pub fn new() -> Self {
+        HostModule::default()
+    }
+
+    /// Adds a member holding `value`.
+    pub fn value(mut self, name: &str, value: impl Into<VarType>) -> Self {
+        self.members
+            .push((name.to_owned(), HostMember::Value(value.into())));
+        self
+    }
+
+    /// Adds a node running `func`, which may capture state of the host.
+    pub fn node(
+        mut self,
+        name: &str,
+        func: impl Fn(&ContextRc, &Vec<VarType>) -> Result<VarType, CodeExecError> + 'static,
+    ) -> Self {
+        self.members
+            .push((name.to_owned(), HostMember::Node(Rc::new(func))));
+        self
+    }
+}
+
+impl IModule for HostModule {
+    fn exec(&self, ctx: &ContextRc) -> Result<Mess, CodeExecError> {
+        let mut mess = Mess::new();
+        for (name, member) in &self.members {
+            let value = match member {
+                HostMember::Value(value) => value.clone(),
+                HostMember::Node(func) => NativeNode::func_as_vartype(ctx, func.clone()),
+            };
+            mess.set(name, value);
+        }
+        Ok(mess)
+    }
+}
+
+/// Runs lIIl code for a host application. Everything evaluated shares one
+/// scope, so variables and nodes survive between calls to `eval`.
+pub struct Interpreter {
+    module: CodeModule,
+}
+
+impl Interpreter {
+    const MODULE_FILE: &'static str = "<host>";
+
+    pub fn new() -> Self {
+        Interpreter::with_module_file(Interpreter::MODULE_FILE)
+    }
+
+    /// An interpreter whose code reports errors as coming from `file` in the
+    /// working directory.
+    pub(crate) fn with_module_file(file: &str) -> Self {
+        let root = Context::root_rc();
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let path = cwd.join(file);
+        Interpreter {
+            module: CodeModule::new("lIIl", path.to_str().unwrap_or_default(), &root, true),
+        }
+    }
+
+    /// Scope the evaluated code runs in.
+    pub fn ctx(&self) -> ContextRc {
+        self.module.ctx.clone()
+    }
+
+    pub fn global(&self) -> GlobalRc {
+        self.module.ctx.borrow().get_global()
+    }
+
+    /// Makes random decisions repeat those of earlier runs with `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.global().borrow_mut().reseed(seed);
+    }
+
+    /// Makes `module` available to `rm name.`, replacing any module of that
+    /// name.
+    pub fn register_module(&mut self, name: &str, module: HostModule) {
+        let module_name = name.to_owned();
+        self.global().borrow_mut().builtin_modules.add_factory(
+            name,
+            Rc::new(move |parent: &ContextRc| -> Module {
+                Module::Native(NativeModule::new(
+                    &module_name,
+                    &Module::builtin_path(&module_name),
+                    parent,
+                    Box::new(module.clone()),
+                ))
+            }),
+        );
+    }
+
+    /// Sets a variable visible to all lIIl code, including modules it loads.
+    pub fn set(&mut self, name: &str, value: impl Into<VarType>) {
+        let root = self.module.ctx.borrow().get_root();
+        root.borrow().set_symbol(name, value.into());
+    }
+
+    /// Value of the variable `name`, or `None` if it is not set or `N0`.
+    pub fn get(&self, name: &str) -> Option<VarType> {
+        self.module
+            .ctx
+            .borrow()
+            .get_symbol(name)
+            .filter(|value| !matches!(value, VarType::Nzero))
+    }
+
+    /// Runs `source` and returns the value of its `=>` statement, or of its
+    /// last statement if that is an expression. Otherwise returns `N0`.
+    pub fn eval(&mut self, source: &str) -> Result<VarType, CodeExecError> {
+        self.module.stmts = Statements::new();
+        parse(&mut self.module, source)?;
+        let stmts = std::mem::replace(&mut self.module.stmts, Statements::new());
+        let ctx = self.ctx();
+        let chunk = Compiler::compile_script(&stmts.stmts, &ctx)?;
+        Context::with(&ctx, || vm::run(&chunk, &ctx))
+    }
+
+    /// Runs the file at `path` like `eval`. Modules it loads with `rm` are
+    /// looked up relative to the file.
+    pub fn eval_file(&mut self, path: &str) -> Result<VarType, CodeExecError> {
+        let io_error =
+            |e: std::io::Error| CodeExecError::new_str(format!("Cannot read {}: {}", path, e));
+        let source = fs::read_to_string(path).map_err(io_error)?;
+        let abs_path = fs::canonicalize(path).map_err(io_error)?;
+        let abs_path = abs_path
+            .to_str()
+            .ok_or_else(|| CodeExecError::new_str(format!("Path {} is not valid UTF-8", path)))?;
+        let host_path = std::mem::replace(&mut self.module.path, Path::new(abs_path));
+        let result = self.eval(&source);
+        self.module.path = host_path;
+        result
+    }
+
+    /// Calls the node in the variable `name` with `args`.
+    pub fn call(&mut self, name: &str, args: Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let value = self.module.ctx.borrow().get_symbol_or_err(name)?;
+        let node = match &value {
+            VarType::Ref(data) => match &*data.borrow() {
+                MemData::Node(node) => Some(node.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        match node {
+            Some(mut node) => node.exec(&args),
+            None => Err(CodeExecError::new_str(format!(
+                "Cannot call non-node {:?}",
+                value
+            ))),
+        }
+    }
+
+    fn new_object(&self, obj: Object) -> VarType {
+        let rc = MemData::new_rc(MemData::Object(obj));
+        self.global().borrow_mut().register_object(&rc);
+        VarType::Ref(rc)
+    }
+
+    /// A new `lol` with `members`.
+    pub fn object<'a>(&self, members: impl IntoIterator<Item = (&'a str, VarType)>) -> VarType {
+        let mut obj = Object::new();
+        for (name, value) in members {
+            obj.set(name, value);
+        }
+        self.new_object(obj)
+    }
+
+    /// A new `lol` list of `items`, in the layout of `ll`.
+    pub fn list(&self, items: impl IntoIterator<Item = VarType>) -> VarType {
+        self.new_object(Object::list(items))
+    }
+
+    /// Members of the `lol` `value` that are not `N0`, in the order they were
+    /// first set.
+    pub fn members(value: &VarType) -> Option<Vec<(String, VarType)>> {
+        let VarType::Ref(data) = value else {
+            return None;
+        };
+        let MemData::Object(obj) = &*data.borrow() else {
+            return None;
+        };
+        let members = obj
+            .keys()
+            .into_iter()
+            .filter_map(|key| obj.get(&key).map(|value| (key, value)))
+            .filter(|(_, value)| !matches!(value, VarType::Nzero))
+            .collect();
+        Some(members)
+    }
+
+    /// Items of the `lol` list `value`, in the layout of `ll`.
+    pub fn items(value: &VarType) -> Option<Vec<VarType>> {
+        let VarType::Ref(data) = value else {
+            return None;
+        };
+        let MemData::Object(obj) = &*data.borrow() else {
+            return None;
+        };
+        let len = match obj.get(LEN_KEY) {
+            Some(VarType::Int(len)) => len,
+            _ => return None,
+        };
+        let items = (0..len)
+            .map(|i| obj.get(&i.to_string()).unwrap_or_default())
+            .collect();
+        Some(items)
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Interpreter {
+    /// The root scope and the global state keep each other alive, as do nodes
+    /// and the scopes they capture. Clearing both scopes and collecting frees
+    /// everything the interpreter created.
+    fn drop(&mut self) {
+        let global = self.global();
+        let root = self.module.ctx.borrow().get_root();
+        let mut values = Vec::new();
+        for ctx in [&self.module.ctx, &root] {
+            if let MemData::Mess(mess) = &mut *ctx.borrow().get_mess().borrow_mut() {
+                values.extend(mess.clear());
+            }
+        }
+        drop(values);
+        global.borrow_mut().context_root = None;
+        global.borrow_mut().collect();
+    }
+}
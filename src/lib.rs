@@ -3,14 +3,18 @@
 
 pub mod data;
 pub mod expr;
+pub mod interpreter;
 pub mod module;
 pub mod parser;
+pub mod repl;
 pub mod statement;
+pub mod test_runner;
 pub mod utils;
+pub mod vm;
 
 #[cfg(test)]
 mod test_utils {
-    use crate::{module::CodeModule, parser::parse_file};
+    use crate::{module::CodeModule, parser::parse_file, statement::CodeExecError};
 
     pub fn exec_program(file: &str) -> CodeModule {
         let path = format!("tests/{}", file);
@@ -18,6 +22,14 @@ mod test_utils {
         module.exec().unwrap();
         module
     }
+
+    pub fn exec_program_err(file: &str) -> CodeExecError {
+        let path = format!("tests/{}", file);
+        match parse_file(&path, None).and_then(|mut module| module.exec()) {
+            Ok(_) => panic!("{} should fail", file),
+            Err(e) => e,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -78,11 +90,100 @@ mod unit_tests {
     fn test_lib() {
         exec_program("unit/lib/main.lIIl");
     }
+
+    #[test]
+    fn test_scope() {
+        exec_program("unit/scope.lIIl");
+    }
+
+    #[test]
+    fn test_ut() {
+        exec_program("unit/ut.lIIl");
+    }
+
+    #[test]
+    fn test_hdd() {
+        exec_program("unit/hdd.lIIl");
+    }
+
+    #[test]
+    fn test_alu() {
+        exec_program("unit/alu.lIIl");
+    }
+
+    #[test]
+    fn test_nic() {
+        exec_program("unit/nic.lIIl");
+    }
+
+    #[test]
+    fn test_mmu() {
+        exec_program("unit/mmu.lIIl");
+    }
+
+    #[test]
+    fn test_loop_control() {
+        exec_program("unit/loop_control.lIIl");
+    }
+
+    #[test]
+    fn test_else() {
+        exec_program("unit/else.lIIl");
+    }
+
+    #[test]
+    fn test_try() {
+        exec_program("unit/try.lIIl");
+    }
+
+    #[test]
+    fn test_ssd() {
+        exec_program("unit/ssd.lIIl");
+    }
+
+    #[test]
+    fn test_fpga() {
+        exec_program("unit/fpga.lIIl");
+    }
+
+    #[test]
+    fn test_ram() {
+        exec_program("unit/ram.lIIl");
+    }
+
+    #[test]
+    fn test_node_expr() {
+        exec_program("unit/node_expr.lIIl");
+    }
+
+    #[test]
+    fn test_destructure() {
+        exec_program("unit/destructure.lIIl");
+    }
+
+    #[test]
+    fn test_tuple() {
+        exec_program("unit/tuple.lIIl");
+    }
+
+    #[test]
+    fn test_params() {
+        exec_program("unit/params.lIIl");
+    }
+
+    #[test]
+    fn test_bigint() {
+        exec_program("unit/bigint.lIIl");
+    }
 }
 
 #[cfg(test)]
 mod program_tests {
-    use crate::test_utils::exec_program;
+    use crate::{
+        data::{context::Context, module::CODE_MODULES},
+        parser::parse_str,
+        test_utils::exec_program,
+    };
 
     #[test]
     fn test_gcd() {
@@ -99,6 +200,21 @@ mod program_tests {
         exec_program("programs/ll_test.lIIl");
     }
 
+    #[test]
+    fn test_builtin_code_modules_parse() {
+        let root = Context::root_rc();
+        for &(name, source) in CODE_MODULES {
+            if let Err(e) = parse_str(name, source, &root) {
+                panic!("Builtin module {} does not parse: {}", name, e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_local_module_shadows_builtin() {
+        exec_program("programs/shadow/main.lIIl");
+    }
+
     #[test]
     fn test_empty_call() {
         exec_program("programs/empty_call.lIIl");
@@ -130,4 +246,322 @@ mod program_tests {
     fn test_map_reduce() {
         exec_program("programs/map_reduce.lIIl");
     }
+
+    #[test]
+    fn test_map_reduce_anon() {
+        exec_program("programs/map_reduce_anon.lIIl");
+    }
+}
+
+#[cfg(test)]
+mod seed_tests {
+    use crate::{data::variable::VarType, parser::parse_file};
+
+    fn run_seeded(seed: u64) -> Vec<String> {
+        let mut module = parse_file("tests/programs/seeded.lIIl", None).unwrap();
+        let global = module.ctx.borrow().get_global();
+        global.borrow_mut().reseed(seed);
+        module.exec().unwrap();
+        ["a", "b", "c", "heads", "picked"]
+            .iter()
+            .map(|name| {
+                let value = module.ctx.borrow().get_symbol(name);
+                value.unwrap_or(VarType::Nzero).to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_same_seed_same_run() {
+        assert_eq!(run_seeded(233), run_seeded(233));
+        assert_eq!(run_seeded(0), run_seeded(0));
+    }
+
+    #[test]
+    fn test_different_seed_different_run() {
+        assert_ne!(run_seeded(1), run_seeded(2));
+    }
+}
+
+#[cfg(test)]
+mod error_tests {
+    use crate::{data::variable::VarType, test_utils::exec_program_err};
+
+    #[test]
+    fn test_symbol_not_found_location() {
+        let e = exec_program_err("errors/symbol_not_found.lIIl");
+        assert_eq!(e.desc(), "Symbol b not found.");
+        let span = e.span().unwrap();
+        assert!(span.path().ends_with("tests/errors/symbol_not_found.lIIl"));
+        assert_eq!(span.line_col(), (2, 12));
+        assert_eq!(span.as_str(), "b");
+    }
+
+    #[test]
+    fn test_type_error_location() {
+        let e = exec_program_err("errors/type_error.lIIl");
+        let span = e.span().unwrap();
+        assert_eq!(span.line_col(), (3, 6));
+        assert_eq!(span.as_str(), "x - y");
+        assert!(e.to_string().ends_with("3 | mk z x - y.\n  |      ^^^^^"));
+    }
+
+    #[test]
+    fn test_tab_indented_location() {
+        let e = exec_program_err("errors/tab_indent.lIIl");
+        assert_eq!(e.span().unwrap().line_col(), (2, 7));
+        assert!(e.to_string().ends_with("2 | \tmk y x - \"a\".\n  | \t     ^^^^^^^"));
+    }
+
+    #[test]
+    fn test_traceback() {
+        let e = exec_program_err("errors/traceback.lIIl");
+        let names: Vec<&str> = e.trace().iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["hasu", "push", "outer"]);
+        assert_eq!(e.trace()[0].path.as_deref(), Some("<module>/ll.lIIl"));
+        assert_eq!(e.trace()[0].call_site.line_col(), (4, 5));
+        assert_eq!(e.trace()[2].call_site.as_str(), "1 >> outer");
+        let outer_path = e.trace()[2].path.as_deref().unwrap();
+        assert!(outer_path.ends_with("tests/errors/traceback.lIIl"));
+        assert!(e.span().unwrap().path().ends_with("ll.lIIl"));
+        let report = e.report();
+        assert!(report.starts_with("Traceback (most recent call last):\n  at "));
+        assert!(report.contains("in outer ("));
+    }
+
+    #[test]
+    fn test_break_outside_loop() {
+        let e = exec_program_err("errors/break_outside_loop.lIIl");
+        assert_eq!(e.desc(), "owari outside of a loop");
+        assert_eq!(e.span().unwrap().line_col(), (2, 10));
+    }
+
+    #[test]
+    fn test_uncaught_yeet() {
+        let e = exec_program_err("errors/uncaught_yeet.lIIl");
+        assert_eq!(e.desc(), "negative input");
+        assert_eq!(e.span().unwrap().line_col(), (2, 14));
+        assert_eq!(e.trace()[0].name, "check");
+        assert!(matches!(e.value(), VarType::String(s) if s == "negative input"));
+    }
+
+    #[test]
+    fn test_rethrow_keeps_location() {
+        let e = exec_program_err("errors/rethrow.lIIl");
+        assert_eq!(e.desc(), "Type error: Int(1), String(a)");
+        assert_eq!(e.span().unwrap().line_col(), (2, 10));
+        let names: Vec<&str> = e.trace().iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["inner", "outer"]);
+    }
+
+    #[test]
+    fn test_syntax_error_location() {
+        let e = exec_program_err("errors/syntax_error.lIIl");
+        assert!(e.desc().starts_with("Syntax error"));
+        assert_eq!(e.span().unwrap().line_col(), (2, 9));
+    }
+
+    #[test]
+    fn test_param_default_order() {
+        let e = exec_program_err("errors/param_default_order.lIIl");
+        assert_eq!(
+            e.desc(),
+            "Parameter b needs a default, as it follows one with a default"
+        );
+        assert_eq!(e.span().unwrap().line_col(), (2, 14));
+    }
+}
+
+#[cfg(test)]
+mod repl_tests {
+    use crate::repl::{open_braces, Repl};
+
+    #[test]
+    fn test_repl_keeps_state() {
+        let mut repl = Repl::new();
+        repl.eval("rm ut. mk x 1.\n").unwrap();
+        repl.eval("inc << a {\n  => a + 1.\n}\n").unwrap();
+        repl.eval("mk x x >> inc.\n").unwrap();
+        repl.eval("x = 2 >> assert@ut.\n").unwrap();
+        assert!(repl.eval("y.\n").is_err());
+        repl.eval("x.\nthat = 2 >> assert@ut.\n").unwrap();
+    }
+
+    #[test]
+    fn test_open_braces() {
+        assert_eq!(open_braces("f << a {\n"), 1);
+        assert_eq!(open_braces("f << a {\n  => a.\n}\n"), 0);
+        assert_eq!(open_braces("\"{\" >> op@cpu.\n"), 0);
+        assert_eq!(open_braces("<-- {\n"), 0);
+        assert_eq!(open_braces("vvv\n{\n^^^\n"), 0);
+        assert_eq!(open_braces("vvvv { ^^^^ f << a {\n"), 1);
+        assert_eq!(open_braces("vvv\n{\n"), 1);
+        assert_eq!(open_braces("mk navvv 1.\n"), 0);
+        assert_eq!(open_braces("mk vvvx 1.\n"), 0);
+        assert_eq!(open_braces("f << navvv {\n"), 1);
+        assert_eq!(open_braces("mk a_v-- {\n"), 1);
+        assert_eq!(open_braces("v-- {\nvvv { ^^^ f << a {\n"), 1);
+        assert_eq!(open_braces("loli x < 1 { '}'\n"), 1);
+    }
+}
+
+#[cfg(test)]
+mod runner_tests {
+    use std::path::Path;
+
+    use crate::test_runner::TestRunner;
+
+    #[test]
+    fn test_discover_and_run() {
+        let runner = TestRunner::new(Some(0));
+        let cases = TestRunner::discover(Path::new("tests/runner")).unwrap();
+        let results: Vec<(String, bool)> = cases
+            .iter()
+            .map(|case| runner.run_case(case))
+            .map(|result| (result.name.replace('\\', "/"), result.passed()))
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                ("tests/runner/nodes.lIIl::test_first_bump".to_owned(), true),
+                ("tests/runner/nodes.lIIl::test_second_bump".to_owned(), true),
+                ("tests/runner/nodes.lIIl::test_fails".to_owned(), false),
+                ("tests/runner/plain.lIIl".to_owned(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assert_failure_is_error() {
+        let case = TestRunner::discover(Path::new("tests/runner/nodes.lIIl"))
+            .unwrap()
+            .into_iter()
+            .find(|case| case.node.as_deref() == Some("test_fails"))
+            .unwrap();
+        let result = TestRunner::new(Some(0)).run_case(&case);
+        let e = result.error.unwrap();
+        assert_eq!(e.desc(), "Assertion failed: one is not two");
+        assert_eq!(e.span().unwrap().line_col(), (19, 5));
+    }
+}
+
+#[cfg(test)]
+mod interpreter_tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        data::variable::VarType,
+        interpreter::{HostModule, Interpreter},
+        parser::parse_file,
+    };
+
+    #[test]
+    fn test_eval_keeps_state() {
+        let mut interp = Interpreter::new();
+        interp.eval("mk x 20.").unwrap();
+        interp.eval("add << a | b {\n  => a + b.\n}\n").unwrap();
+        let value = interp.eval("=> x | 22 >> add.").unwrap();
+        assert_eq!(i64::try_from(value).unwrap(), 42);
+        assert!(matches!(interp.get("x"), Some(VarType::Int(20))));
+        assert!(interp.get("missing").is_none());
+    }
+
+    #[test]
+    fn test_tuple_display_reads_back() {
+        let mut interp = Interpreter::new();
+        for source in ["[5]", "['a\"e\u{301}\\n']", "1 | [2]", "[(1 | 2)]", "[[3]]"] {
+            let shown = interp.eval(&format!("{}.", source)).unwrap().to_string();
+            let again = interp.eval(&format!("{}.", shown)).unwrap();
+            assert_eq!(again.to_string(), shown);
+        }
+    }
+
+    #[test]
+    fn test_eval_trailing_expression() {
+        let mut interp = Interpreter::new();
+        let value = interp.eval("mk x 2.\nx * 3.").unwrap();
+        assert!(matches!(value, VarType::Int(6)));
+        assert!(matches!(interp.get("that"), Some(VarType::Int(6))));
+        assert!(matches!(interp.eval("x.\nmk y 1.").unwrap(), VarType::Nzero));
+        assert!(matches!(interp.eval("N0.").unwrap(), VarType::Nzero));
+        assert!(matches!(interp.get("that"), Some(VarType::Int(2))));
+    }
+
+    #[test]
+    fn test_host_module() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let sink = log.clone();
+        let mut interp = Interpreter::new();
+        interp.register_module(
+            "host",
+            HostModule::new()
+                .value("version", 3)
+                .node("log", move |_, args| {
+                    sink.borrow_mut().extend(args.iter().map(|arg| arg.to_string()));
+                    Ok(VarType::Nzero)
+                }),
+        );
+        let value = interp
+            .eval("rm host.\n\"hi\" | 1 >> log@host.\n=> version@host.")
+            .unwrap();
+        assert_eq!(i64::try_from(value).unwrap(), 3);
+        assert_eq!(*log.borrow(), vec!["hi", "1"]);
+    }
+
+    #[test]
+    fn test_set_call_and_convert() {
+        let mut interp = Interpreter::new();
+        interp.set("scale", 2.5);
+        let xs = interp.list([1.into(), 2.into(), 3.into()]);
+        interp.set("xs", xs);
+        interp
+            .eval("total << u {\n  mk sum 0.\n  loli x qaq xs { mk sum sum + x. }\n  => sum * scale.\n}\n")
+            .unwrap();
+        let value = interp.call("total", vec![VarType::Nzero]).unwrap();
+        assert_eq!(f64::try_from(value).unwrap(), 15.0);
+
+        let value = interp.eval("mk r lol.\nmk name@r \"lIIl\".\n=> r.").unwrap();
+        let members = Interpreter::members(&value).unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(String::try_from(members[0].1.clone()).unwrap(), "lIIl");
+        let items = Interpreter::items(&interp.get("xs").unwrap()).unwrap();
+        assert_eq!(items.len(), 3);
+        assert!(i64::try_from(VarType::from("1")).is_err());
+    }
+
+    #[test]
+    fn test_errors_are_returned() {
+        let mut interp = Interpreter::new();
+        let e = interp.eval_file("tests/no_such_file.lIIl").unwrap_err();
+        assert!(e.desc().starts_with("Cannot read tests/no_such_file.lIIl"));
+        assert!(interp.eval("mk x .").unwrap_err().desc().starts_with("Syntax error"));
+        assert_eq!(
+            interp.eval("rm no_such_module.").unwrap_err().desc(),
+            "Module no_such_module not found"
+        );
+        assert!(interp.call("nothing", Vec::new()).is_err());
+        interp.eval("mk n 1.").unwrap();
+        assert!(interp.call("n", Vec::new()).is_err());
+        // The interpreter is still usable.
+        assert!(interp.eval("=> n.").is_ok());
+        assert!(parse_file("tests/no_such_file.lIIl", None).is_err());
+    }
+
+    #[test]
+    fn test_eval_file() {
+        let mut interp = Interpreter::new();
+        interp.eval_file("tests/unit/rm.lIIl").unwrap();
+        assert!(interp.get("y").is_some());
+    }
+
+    #[test]
+    fn test_drop_frees_everything() {
+        let mut interp = Interpreter::new();
+        let global = Rc::downgrade(&interp.global());
+        interp
+            .eval("rm ut.\nadder << base {\n  add << x {\n    => base + x.\n  }\n  => add.\n}\nmk add10 10 >> adder.\nmk a lol.\nmk a@a a.\n")
+            .unwrap();
+        drop(interp);
+        assert!(global.upgrade().is_none());
+    }
 }
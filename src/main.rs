@@ -1,24 +1,94 @@
 // For package name lIIl.
 #![allow(non_snake_case)]
 
-use clap::Parser;
+use std::process::ExitCode;
+
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
+use data::global::Global;
 mod data;
 mod expr;
+// The REPL uses only part of the embedding API.
+#[allow(dead_code)]
+mod interpreter;
 mod module;
 mod parser;
+mod repl;
 mod statement;
+mod test_runner;
 mod utils;
+mod vm;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    source_file: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Program to run. Starts an interactive session when omitted.
+    source_file: Option<String>,
+    /// Seed for `maybe` and `!`. Defaults to the LIIL_SEED environment
+    /// variable, or a random seed that is printed if the program fails.
+    #[arg(long, global = true)]
+    seed: Option<u64>,
 }
 
-fn main() {
-    let args = Args::parse();
-    let mut module = parser::parse_file(&args.source_file, None).unwrap();
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run every `.lIIl` file under a directory, or its `test_*` nodes, as a
+    /// test.
+    Test {
+        /// Directory or file to collect tests from.
+        #[arg(default_value = "tests/unit")]
+        dir: String,
+    },
+}
+
+fn run_file(source_file: &str, seed: Option<u64>) -> ExitCode {
+    let mut module = match parser::parse_file(source_file, None) {
+        Ok(module) => module,
+        Err(e) => {
+            println!("{}", e.report());
+            return ExitCode::FAILURE;
+        }
+    };
+    let global = module.ctx.borrow().get_global();
+    if let Some(seed) = seed {
+        global.borrow_mut().reseed(seed);
+    }
     if let Err(e) = module.exec() {
-        println!("Error: {:?}", e);
+        println!("{}", e.report());
+        if seed.is_none() {
+            let seed = global.borrow().seed;
+            println!("Random seed: {seed}. Rerun with --seed {seed} to reproduce.");
+        }
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let seed = match args.seed {
+        Some(seed) => Some(seed),
+        None => match Global::env_seed() {
+            Ok(seed) => seed,
+            Err(e) => Args::command().error(ErrorKind::InvalidValue, e).exit(),
+        },
+    };
+    if let Some(Command::Test { dir }) = args.command {
+        if test_runner::TestRunner::new(seed).run(&dir) {
+            return ExitCode::SUCCESS;
+        }
+        return ExitCode::FAILURE;
+    }
+    match args.source_file {
+        Some(source_file) => run_file(&source_file, seed),
+        None => {
+            let mut repl = repl::Repl::new();
+            if let Some(seed) = seed {
+                repl.reseed(seed);
+            }
+            repl.run();
+            ExitCode::SUCCESS
+        }
     }
 }
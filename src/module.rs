@@ -6,9 +6,17 @@ use crate::{
     },
     statement::{CodeExecError, Statements},
     utils::path::Path,
+    vm::{self, compiler::Compiler},
 };
 pub mod ai;
+pub mod alu;
 pub mod cpu;
+pub mod fpga;
+pub mod hdd;
+pub mod mmu;
+pub mod nic;
+pub mod ram;
+pub mod ssd;
 pub mod test;
 pub mod tp;
 
@@ -69,8 +77,9 @@ impl CodeModule {
         ret
     }
     pub fn exec(&mut self) -> Result<VarType, CodeExecError> {
+        let chunk = Compiler::compile_stmts(&self.stmts.stmts, &self.ctx)?;
         Context::with(&self.ctx, || {
-            self.stmts.exec(&self.ctx)?;
+            vm::run(&chunk, &self.ctx)?;
             Ok(VarType::Ref(self.ctx.borrow().get_mess()))
         })
     }
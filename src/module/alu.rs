@@ -0,0 +1,233 @@
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
+
+use crate::{
+    data::node::{NativeFnPtr, NativeNode},
+    data::{context::ContextRc, data::Mess, module::ModuleFactoryManager, variable::VarType},
+    expr::{CompareExpr, CompareOp},
+    statement::CodeExecError,
+};
+
+use super::{IModule, Module, NativeModule};
+
+/// Math functions. Like arithmetic, they promote integers to `Float` where
+/// needed. Results outside the domain of a function, such as `-1 >>
+/// sqrt@alu`, are `N0`.
+pub struct AluModule {
+    members: Vec<(&'static str, VarType)>,
+}
+
+impl AluModule {
+    pub const NAME: &str = "alu";
+
+    /// Most bits an integer power may have.
+    const MAX_POW_BITS: u64 = 1 << 20;
+
+    fn arg<'a>(name: &str, args: &'a [VarType], idx: usize) -> Result<&'a VarType, CodeExecError> {
+        args.get(idx).ok_or_else(|| {
+            CodeExecError::new_str(format!(
+                "{}@alu requires {} arguments, got {}",
+                name,
+                idx + 1,
+                args.len()
+            ))
+        })
+    }
+
+    fn float_arg(name: &str, args: &[VarType], idx: usize) -> Result<f64, CodeExecError> {
+        match AluModule::arg(name, args, idx)? {
+            VarType::Int(value) => Ok(*value as f64),
+            VarType::BigInt(value) => Ok(value.to_f64().unwrap_or(f64::NAN)),
+            VarType::Float(value) => Ok(*value),
+            value => Err(CodeExecError::new_str(format!(
+                "{}@alu expects a number, got {:?}",
+                name, value
+            ))),
+        }
+    }
+
+    fn int_arg(name: &str, args: &[VarType], idx: usize) -> Result<BigInt, CodeExecError> {
+        match AluModule::arg(name, args, idx)? {
+            VarType::Int(value) => Ok(BigInt::from(*value)),
+            VarType::BigInt(value) => Ok((**value).clone()),
+            value => Err(CodeExecError::new_str(format!(
+                "{}@alu expects an integer, got {:?}",
+                name, value
+            ))),
+        }
+    }
+
+    fn float(value: f64) -> VarType {
+        if value.is_finite() {
+            VarType::Float(value)
+        } else {
+            VarType::Nzero
+        }
+    }
+
+    fn unary(name: &str, args: &[VarType], f: fn(f64) -> f64) -> Result<VarType, CodeExecError> {
+        let x = AluModule::float_arg(name, args, 0)?;
+        Ok(AluModule::float(f(x)))
+    }
+
+    fn binary(
+        name: &str,
+        args: &[VarType],
+        f: fn(f64, f64) -> f64,
+    ) -> Result<VarType, CodeExecError> {
+        let x = AluModule::float_arg(name, args, 0)?;
+        let y = AluModule::float_arg(name, args, 1)?;
+        Ok(AluModule::float(f(x, y)))
+    }
+
+    /// Rounds a `Float` to an integer with `f`. Integers are returned as they
+    /// are.
+    fn round(name: &str, args: &[VarType], f: fn(f64) -> f64) -> Result<VarType, CodeExecError> {
+        if let Some(value @ (VarType::Int(_) | VarType::BigInt(_))) = args.first() {
+            return Ok(value.clone());
+        }
+        let x = f(AluModule::float_arg(name, args, 0)?);
+        Ok(BigInt::from_f64(x).map_or(VarType::Nzero, VarType::from))
+    }
+
+    fn abs(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        match args.first() {
+            Some(VarType::Int(value)) => Ok(match value.checked_abs() {
+                Some(value) => VarType::Int(value),
+                None => VarType::from(BigInt::from(*value).abs()),
+            }),
+            Some(VarType::BigInt(value)) => Ok(VarType::from(value.abs())),
+            _ => AluModule::unary("abs", args, f64::abs),
+        }
+    }
+
+    /// Integer powers stay integers unless the exponent is negative.
+    fn pow(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let base = match args.first() {
+            Some(VarType::Int(base)) => Some(BigInt::from(*base)),
+            Some(VarType::BigInt(base)) => Some((**base).clone()),
+            _ => None,
+        };
+        if let (Some(base), Some(VarType::Int(exp))) = (base, args.get(1)) {
+            if *exp >= 0 {
+                let exp = *exp as u64;
+                // |base| to the power of exp has at least this many bits.
+                let bits = (base.bits().saturating_sub(1)).saturating_mul(exp);
+                if bits > AluModule::MAX_POW_BITS {
+                    return Err(CodeExecError::new_str(format!(
+                        "Integer too large: {} to the power of {}",
+                        base, exp
+                    )));
+                }
+                return Ok(VarType::from(num_traits::Pow::pow(base, exp)));
+            }
+        }
+        AluModule::binary("pow", args, f64::powf)
+    }
+
+    /// Natural logarithm, or logarithm to the base given as second argument.
+    fn log(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        if args.len() > 1 {
+            return AluModule::binary("log", args, f64::log);
+        }
+        AluModule::unary("log", args, f64::ln)
+    }
+
+    /// Smallest or largest argument, by the same rules as `<` and `>`.
+    fn extreme(
+        ctx: &ContextRc,
+        args: &[VarType],
+        name: &str,
+        op: CompareOp,
+    ) -> Result<VarType, CodeExecError> {
+        let mut best = AluModule::arg(name, args, 0)?;
+        for arg in &args[1..] {
+            if bool::from(&CompareExpr::apply(ctx, op, arg.clone(), best.clone())?) {
+                best = arg;
+            }
+        }
+        Ok(best.clone())
+    }
+
+    fn gcd(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        AluModule::arg("gcd", args, 0)?;
+        let mut result = BigInt::zero();
+        for idx in 0..args.len() {
+            let mut b = AluModule::int_arg("gcd", args, idx)?.abs();
+            while !b.is_zero() {
+                let rem = &result % &b;
+                result = std::mem::replace(&mut b, rem);
+            }
+        }
+        Ok(VarType::from(result))
+    }
+
+    pub fn new(parent: &ContextRc) -> AluModule {
+        let node = |func: NativeFnPtr| NativeNode::as_vartype(parent, func);
+        AluModule {
+            members: vec![
+                ("pi", VarType::Float(std::f64::consts::PI)),
+                ("e", VarType::Float(std::f64::consts::E)),
+                ("sin", node(|_, args| Self::unary("sin", args, f64::sin))),
+                ("cos", node(|_, args| Self::unary("cos", args, f64::cos))),
+                ("tan", node(|_, args| Self::unary("tan", args, f64::tan))),
+                ("asin", node(|_, args| Self::unary("asin", args, f64::asin))),
+                ("acos", node(|_, args| Self::unary("acos", args, f64::acos))),
+                ("atan", node(|_, args| Self::unary("atan", args, f64::atan))),
+                (
+                    "atan2",
+                    node(|_, args| Self::binary("atan2", args, f64::atan2)),
+                ),
+                ("sqrt", node(|_, args| Self::unary("sqrt", args, f64::sqrt))),
+                ("exp", node(|_, args| Self::unary("exp", args, f64::exp))),
+                ("log", node(Self::log)),
+                ("pow", node(Self::pow)),
+                ("abs", node(Self::abs)),
+                (
+                    "floor",
+                    node(|_, args| Self::round("floor", args, f64::floor)),
+                ),
+                ("ceil", node(|_, args| Self::round("ceil", args, f64::ceil))),
+                (
+                    "round",
+                    node(|_, args| Self::round("round", args, f64::round)),
+                ),
+                (
+                    "min",
+                    node(|ctx, args| Self::extreme(ctx, args, "min", CompareOp::Less)),
+                ),
+                (
+                    "max",
+                    node(|ctx, args| Self::extreme(ctx, args, "max", CompareOp::Greater)),
+                ),
+                ("gcd", node(Self::gcd)),
+            ],
+        }
+    }
+
+    pub fn register(manager: &mut ModuleFactoryManager) {
+        manager.add_factory(
+            AluModule::NAME,
+            Rc::new(move |parent: &ContextRc| -> Module {
+                Module::Native(NativeModule::new(
+                    AluModule::NAME,
+                    AluModule::NAME,
+                    parent,
+                    Box::new(AluModule::new(parent)),
+                ))
+            }),
+        );
+    }
+}
+
+impl IModule for AluModule {
+    fn exec(&self, _ctx: &ContextRc) -> Result<Mess, CodeExecError> {
+        let mut mess = Mess::new();
+        for (name, value) in &self.members {
+            mess.set(name, value.clone());
+        }
+        Ok(mess)
+    }
+}
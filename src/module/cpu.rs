@@ -16,27 +16,30 @@ pub struct CpuModule {
 impl CpuModule {
     pub const NAME: &str = "cpu";
 
-    fn print(args: &Vec<VarType>, newline: bool) -> Result<VarType, CodeExecError> {
-        let joined = args
+    fn print(
+        ctx: &ContextRc,
+        args: &Vec<VarType>,
+        newline: bool,
+    ) -> Result<VarType, CodeExecError> {
+        let mut joined = args
             .iter()
             .map(|item| item.to_string())
             .collect::<Vec<String>>()
             .join(" ");
         if newline {
-            println!("{}", joined);
-        } else {
-            print!("{}", joined);
+            joined.push('\n');
         }
+        ctx.borrow().get_global().borrow_mut().write(&joined);
         Ok(VarType::Nzero)
     }
 
     pub fn new(parent: &ContextRc) -> CpuModule {
         CpuModule {
-            op: NativeNode::as_vartype(parent, |_: &ContextRc, args: &Vec<VarType>| {
-                Self::print(args, false)
+            op: NativeNode::as_vartype(parent, |ctx: &ContextRc, args: &Vec<VarType>| {
+                Self::print(ctx, args, false)
             }),
-            wcop: NativeNode::as_vartype(parent, |_: &ContextRc, args: &Vec<VarType>| {
-                Self::print(args, true)
+            wcop: NativeNode::as_vartype(parent, |ctx: &ContextRc, args: &Vec<VarType>| {
+                Self::print(ctx, args, true)
             }),
         }
     }
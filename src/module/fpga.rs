@@ -0,0 +1,798 @@
+use std::rc::Rc;
+
+use crate::{
+    data::node::{NativeFnPtr, NativeNode},
+    data::{
+        context::ContextRc,
+        data::{MemData, Mess, Object},
+        module::ModuleFactoryManager,
+        variable::VarType,
+    },
+    statement::CodeExecError,
+};
+
+use super::{IModule, Module, NativeModule};
+
+/// Regular expressions. Patterns are matched by characters, with a
+/// backtracking engine that never visits the same state twice, so matching
+/// takes time proportional to the length of the pattern times that of the
+/// text.
+pub struct FpgaModule {
+    members: Vec<(&'static str, VarType)>,
+}
+
+impl FpgaModule {
+    pub const NAME: &str = "fpga";
+
+    fn string_arg<'a>(
+        name: &str,
+        args: &'a [VarType],
+        idx: usize,
+    ) -> Result<&'a str, CodeExecError> {
+        match args.get(idx) {
+            Some(VarType::String(s)) => Ok(s),
+            arg => Err(CodeExecError::new_str(format!(
+                "Argument {} to {}@fpga must be a string, got {:?}",
+                idx + 1,
+                name,
+                arg.unwrap_or(&VarType::Nzero)
+            ))),
+        }
+    }
+
+    /// The text and the compiled pattern of `text | pattern >> name@fpga.`
+    fn text_and_regex(name: &str, args: &[VarType]) -> Result<(Vec<char>, Regex), CodeExecError> {
+        let text = FpgaModule::string_arg(name, args, 0)?;
+        let regex = Regex::new(FpgaModule::string_arg(name, args, 1)?)?;
+        Ok((text.chars().collect(), regex))
+    }
+
+    fn new_object(ctx: &ContextRc, obj: Object) -> VarType {
+        let rc = MemData::new_rc(MemData::Object(obj));
+        ctx.borrow().get_global().borrow_mut().register_object(&rc);
+        VarType::Ref(rc)
+    }
+
+    fn ok(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let (text, regex) = FpgaModule::text_and_regex("ok", args)?;
+        Ok(VarType::Bool(regex.first(&text)?.is_some()))
+    }
+
+    /// `text | pattern >> all@fpga.` Every match, as a list in the layout of
+    /// `ll`.
+    fn all(ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let (text, regex) = FpgaModule::text_and_regex("all", args)?;
+        let matches = regex
+            .matches(&text)?
+            .map(|caps| VarType::String(Regex::group(&text, &caps, 0).unwrap_or_default()));
+        Ok(FpgaModule::new_object(ctx, Object::list(matches)))
+    }
+
+    /// `text | pattern >> cap@fpga.` Groups of the first match as a list in the
+    /// layout of `ll`, the whole match first, with named groups also under
+    /// their names. Groups that did not take part are `N0`, as is the result
+    /// if nothing matches.
+    fn cap(ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let (text, regex) = FpgaModule::text_and_regex("cap", args)?;
+        let Some(caps) = regex.first(&text)? else {
+            return Ok(VarType::Nzero);
+        };
+        let group = |idx| Regex::group(&text, &caps, idx).map_or(VarType::Nzero, VarType::String);
+        let mut obj = Object::list((0..regex.groups).map(group));
+        for (name, idx) in &regex.names {
+            obj.set(name, group(*idx));
+        }
+        Ok(FpgaModule::new_object(ctx, obj))
+    }
+
+    /// `text | pattern | replacement >> rep@fpga.` Replaces every match.
+    /// `$1` or `${1}` in `replacement` stands for a group, `${name}` for a
+    /// named one and `$$` for `$`.
+    fn rep(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let (text, regex) = FpgaModule::text_and_regex("rep", args)?;
+        let pieces = Piece::parse(&regex, FpgaModule::string_arg("rep", args, 2)?)?;
+        let mut result = String::new();
+        let mut last = 0;
+        for caps in regex.matches(&text)? {
+            let (start, end) = (caps[0].unwrap_or(last), caps[1].unwrap_or(last));
+            result.extend(&text[last..start]);
+            for piece in &pieces {
+                match piece {
+                    Piece::Text(s) => result.push_str(s),
+                    Piece::Group(idx) => {
+                        result.push_str(&Regex::group(&text, &caps, *idx).unwrap_or_default())
+                    }
+                }
+            }
+            last = end;
+        }
+        result.extend(&text[last..]);
+        Ok(VarType::String(result))
+    }
+
+    pub fn new(parent: &ContextRc) -> FpgaModule {
+        let node = |func: NativeFnPtr| NativeNode::as_vartype(parent, func);
+        FpgaModule {
+            members: vec![
+                ("ok", node(Self::ok)),
+                ("all", node(Self::all)),
+                ("cap", node(Self::cap)),
+                ("rep", node(Self::rep)),
+            ],
+        }
+    }
+
+    pub fn register(manager: &mut ModuleFactoryManager) {
+        manager.add_factory(
+            FpgaModule::NAME,
+            Rc::new(move |parent: &ContextRc| -> Module {
+                Module::Native(NativeModule::new(
+                    FpgaModule::NAME,
+                    FpgaModule::NAME,
+                    parent,
+                    Box::new(FpgaModule::new(parent)),
+                ))
+            }),
+        );
+    }
+}
+
+impl IModule for FpgaModule {
+    fn exec(&self, _ctx: &ContextRc) -> Result<Mess, CodeExecError> {
+        let mut mess = Mess::new();
+        for (name, value) in &self.members {
+            mess.set(name, value.clone());
+        }
+        Ok(mess)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Perl {
+    Digit,
+    Word,
+    Space,
+}
+
+impl Perl {
+    fn matches(self, c: char) -> bool {
+        match self {
+            Perl::Digit => c.is_ascii_digit(),
+            Perl::Word => c.is_alphanumeric() || c == '_',
+            Perl::Space => c.is_whitespace(),
+        }
+    }
+}
+
+enum ClassItem {
+    Range(char, char),
+    /// A class like `\d`, or `\D` if negated.
+    Perl(Perl, bool),
+}
+
+struct Class {
+    items: Vec<ClassItem>,
+    negated: bool,
+}
+
+impl Class {
+    fn perl(perl: Perl, negated: bool) -> Class {
+        Class {
+            items: vec![ClassItem::Perl(perl, negated)],
+            negated: false,
+        }
+    }
+
+    fn matches(&self, c: char) -> bool {
+        let found = self.items.iter().any(|item| match item {
+            ClassItem::Range(lo, hi) => (*lo..=*hi).contains(&c),
+            ClassItem::Perl(perl, negated) => perl.matches(c) != *negated,
+        });
+        found != self.negated
+    }
+}
+
+/// What a backslash in a pattern stands for.
+enum Escape {
+    Char(char),
+    Perl(Perl, bool),
+    Boundary(bool),
+}
+
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Rc<Class>),
+    Start,
+    End,
+    /// `\b`, or `\B` if false.
+    Boundary(bool),
+    /// A group, capturing if it has an index.
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+    },
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+    groups: usize,
+    names: &'a mut Vec<(String, usize)>,
+}
+
+impl Parser<'_> {
+    /// Deeper nesting is rejected rather than risking the native stack.
+    const MAX_DEPTH: usize = 256;
+    const MAX_REPEAT: u32 = 1000;
+
+    fn error(&self, desc: &str) -> CodeExecError {
+        CodeExecError::new_str(format!("Invalid pattern at {}: {}", self.pos + 1, desc))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn parse(mut self) -> Result<(Node, usize), CodeExecError> {
+        let node = self.alternation()?;
+        match self.peek() {
+            None => Ok((node, self.groups + 1)),
+            Some(_) => Err(self.error("unmatched ')'")),
+        }
+    }
+
+    fn alternation(&mut self) -> Result<Node, CodeExecError> {
+        let mut alts = vec![self.concat()?];
+        while self.eat('|') {
+            alts.push(self.concat()?);
+        }
+        Ok(match alts.len() {
+            1 => alts.pop().unwrap_or(Node::Empty),
+            _ => Node::Alt(alts),
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, CodeExecError> {
+        let mut nodes = Vec::new();
+        while !matches!(self.peek(), None | Some('|' | ')')) {
+            let atom = self.atom()?;
+            nodes.push(self.quantifier(atom)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap_or(Node::Empty),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn atom(&mut self) -> Result<Node, CodeExecError> {
+        let Some(c) = self.next() else {
+            return Err(self.error("unexpected end"));
+        };
+        Ok(match c {
+            '(' => self.group()?,
+            '[' => Node::Class(Rc::new(self.class()?)),
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '\\' => match self.escape()? {
+                Escape::Char(c) => Node::Char(c),
+                Escape::Perl(perl, negated) => Node::Class(Rc::new(Class::perl(perl, negated))),
+                Escape::Boundary(word) => Node::Boundary(word),
+            },
+            '*' | '+' | '?' => {
+                self.pos -= 1;
+                return Err(self.error("nothing to repeat"));
+            }
+            c => Node::Char(c),
+        })
+    }
+
+    fn group(&mut self) -> Result<Node, CodeExecError> {
+        if self.depth == Parser::MAX_DEPTH {
+            return Err(self.error("groups nested too deeply"));
+        }
+        let idx = if self.eat('?') {
+            if self.eat(':') {
+                None
+            } else if self.eat('<') || (self.eat('P') && self.eat('<')) {
+                let name = self.group_name()?;
+                self.groups += 1;
+                self.names.push((name, self.groups));
+                Some(self.groups)
+            } else {
+                return Err(self.error("unknown group kind"));
+            }
+        } else {
+            self.groups += 1;
+            Some(self.groups)
+        };
+        self.depth += 1;
+        let body = self.alternation()?;
+        self.depth -= 1;
+        if !self.eat(')') {
+            return Err(self.error("missing ')'"));
+        }
+        Ok(Node::Group(Box::new(body), idx))
+    }
+
+    fn group_name(&mut self) -> Result<String, CodeExecError> {
+        let mut name = String::new();
+        loop {
+            match self.next() {
+                Some('>') if !name.is_empty() => break,
+                Some(c) if c.is_alphanumeric() || c == '_' => name.push(c),
+                _ => return Err(self.error("invalid group name")),
+            }
+        }
+        if name.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(self.error("group names cannot start with a digit"));
+        }
+        if self.names.iter().any(|(other, _)| *other == name) {
+            return Err(self.error(&format!("duplicate group name {}", name)));
+        }
+        Ok(name)
+    }
+
+    fn escape(&mut self) -> Result<Escape, CodeExecError> {
+        let Some(c) = self.next() else {
+            return Err(self.error("trailing backslash"));
+        };
+        Ok(match c {
+            'd' => Escape::Perl(Perl::Digit, false),
+            'D' => Escape::Perl(Perl::Digit, true),
+            'w' => Escape::Perl(Perl::Word, false),
+            'W' => Escape::Perl(Perl::Word, true),
+            's' => Escape::Perl(Perl::Space, false),
+            'S' => Escape::Perl(Perl::Space, true),
+            'b' => Escape::Boundary(true),
+            'B' => Escape::Boundary(false),
+            'n' => Escape::Char('\n'),
+            't' => Escape::Char('\t'),
+            'r' => Escape::Char('\r'),
+            c if c.is_alphanumeric() => {
+                return Err(self.error(&format!("unknown escape \\{}", c)));
+            }
+            c => Escape::Char(c),
+        })
+    }
+
+    /// A class after its `[`.
+    fn class(&mut self) -> Result<Class, CodeExecError> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        // A `]` right at the start is a character of its own.
+        let mut first = true;
+        loop {
+            let lo = match self.next() {
+                None => return Err(self.error("missing ']'")),
+                Some(']') if !first => break,
+                Some(c) => self.class_char(c)?,
+            };
+            first = false;
+            let lo = match lo {
+                Escape::Char(c) => c,
+                Escape::Perl(perl, negated) => {
+                    items.push(ClassItem::Perl(perl, negated));
+                    continue;
+                }
+                Escape::Boundary(_) => unreachable!(),
+            };
+            let is_range = self.peek() == Some('-')
+                && !matches!(self.chars.get(self.pos + 1), None | Some(']'));
+            if !is_range {
+                items.push(ClassItem::Range(lo, lo));
+                continue;
+            }
+            self.pos += 1;
+            let hi = match self.next().map(|c| self.class_char(c)).transpose()? {
+                Some(Escape::Char(hi)) if hi >= lo => hi,
+                _ => return Err(self.error("invalid range")),
+            };
+            items.push(ClassItem::Range(lo, hi));
+        }
+        Ok(Class { items, negated })
+    }
+
+    fn class_char(&mut self, c: char) -> Result<Escape, CodeExecError> {
+        if c != '\\' {
+            return Ok(Escape::Char(c));
+        }
+        match self.escape()? {
+            Escape::Boundary(_) => Err(self.error("\\b and \\B cannot be in a class")),
+            escape => Ok(escape),
+        }
+    }
+
+    fn quantifier(&mut self, node: Node) -> Result<Node, CodeExecError> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.counts()? {
+                Some(counts) => counts,
+                None => return Ok(node),
+            },
+            _ => return Ok(node),
+        };
+        self.pos += 1;
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}` up to its `}`, or leaves a `{` that
+    /// starts none of them to be a character of its own.
+    fn counts(&mut self) -> Result<Option<(u32, Option<u32>)>, CodeExecError> {
+        let start = self.pos;
+        self.pos += 1;
+        let min = self.number();
+        let max = if self.eat(',') { self.number() } else { min };
+        let (Some(min), Some('}')) = (min, self.peek()) else {
+            self.pos = start;
+            return Ok(None);
+        };
+        if min > Parser::MAX_REPEAT || max.is_some_and(|max| max > Parser::MAX_REPEAT) {
+            return Err(self.error(&format!("counts above {}", Parser::MAX_REPEAT)));
+        }
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error("count range is backwards"));
+        }
+        Ok(Some((min, max)))
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits.parse().ok()
+    }
+}
+
+enum Inst {
+    Char(char),
+    /// Any character but a newline.
+    Any,
+    AnyOrNewline,
+    Class(Rc<Class>),
+    Start,
+    End,
+    Boundary(bool),
+    /// Tries the first target, then the second.
+    Split(usize, usize),
+    Jump(usize),
+    Save(usize),
+    Match,
+}
+
+struct Regex {
+    prog: Vec<Inst>,
+    /// Number of groups, counting the whole match.
+    groups: usize,
+    names: Vec<(String, usize)>,
+}
+
+impl Regex {
+    const MAX_INSTS: usize = 100_000;
+    /// Most states, each an instruction at a position in the text, that a
+    /// match may have to remember visiting: 128 MiB of bits.
+    const MAX_STATES: usize = 1 << 30;
+
+    fn new(pattern: &str) -> Result<Regex, CodeExecError> {
+        let mut names = Vec::new();
+        let parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            depth: 0,
+            groups: 0,
+            names: &mut names,
+        };
+        let (node, groups) = parser.parse()?;
+        // Trying the pattern at each position in turn finds the leftmost match.
+        let mut prog = vec![Inst::Split(3, 1), Inst::AnyOrNewline, Inst::Jump(0)];
+        Regex::compile(&Node::Group(Box::new(node), Some(0)), &mut prog)?;
+        prog.push(Inst::Match);
+        Ok(Regex {
+            prog,
+            groups,
+            names,
+        })
+    }
+
+    fn compile(node: &Node, prog: &mut Vec<Inst>) -> Result<(), CodeExecError> {
+        if prog.len() > Regex::MAX_INSTS {
+            return Err(CodeExecError::new_str("Pattern is too large".to_owned()));
+        }
+        match node {
+            Node::Empty => {}
+            Node::Char(c) => prog.push(Inst::Char(*c)),
+            Node::Any => prog.push(Inst::Any),
+            Node::Class(class) => prog.push(Inst::Class(class.clone())),
+            Node::Start => prog.push(Inst::Start),
+            Node::End => prog.push(Inst::End),
+            Node::Boundary(word) => prog.push(Inst::Boundary(*word)),
+            Node::Group(body, None) => Regex::compile(body, prog)?,
+            Node::Group(body, Some(idx)) => {
+                prog.push(Inst::Save(idx * 2));
+                Regex::compile(body, prog)?;
+                prog.push(Inst::Save(idx * 2 + 1));
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    Regex::compile(node, prog)?;
+                }
+            }
+            Node::Alt(alts) => {
+                let mut jumps = Vec::new();
+                for (i, alt) in alts.iter().enumerate() {
+                    if i + 1 == alts.len() {
+                        Regex::compile(alt, prog)?;
+                        break;
+                    }
+                    let split = prog.len();
+                    prog.push(Inst::Split(split + 1, 0));
+                    Regex::compile(alt, prog)?;
+                    jumps.push(prog.len());
+                    prog.push(Inst::Jump(0));
+                    prog[split] = Inst::Split(split + 1, prog.len());
+                }
+                let end = prog.len();
+                for jump in jumps {
+                    prog[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                for _ in 0..*min {
+                    Regex::compile(node, prog)?;
+                }
+                let split = |body: usize, end: usize| match greedy {
+                    true => Inst::Split(body, end),
+                    false => Inst::Split(end, body),
+                };
+                match max {
+                    None => {
+                        let start = prog.len();
+                        prog.push(Inst::Match);
+                        Regex::compile(node, prog)?;
+                        prog.push(Inst::Jump(start));
+                        prog[start] = split(start + 1, prog.len());
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(prog.len());
+                            prog.push(Inst::Match);
+                            Regex::compile(node, prog)?;
+                        }
+                        let end = prog.len();
+                        for start in splits {
+                            prog[start] = split(start + 1, end);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// One bit for each state, position after position, to mark those
+    /// visited while matching `text`.
+    fn visited(&self, text: &[char]) -> Result<Vec<u64>, CodeExecError> {
+        match self.prog.len().checked_mul(text.len() + 1) {
+            Some(states) if states <= Regex::MAX_STATES => Ok(vec![0; states.div_ceil(64)]),
+            _ => Err(CodeExecError::new_str(
+                "Pattern and text are too large to match".to_owned(),
+            )),
+        }
+    }
+
+    /// Start and end of each group of the first match.
+    fn first(&self, text: &[char]) -> Result<Option<Vec<Option<usize>>>, CodeExecError> {
+        Ok(self.captures(text, 0, &mut self.visited(text)?))
+    }
+
+    /// Start and end of each group of the first match at or after `from`.
+    /// States left `visited` by earlier calls are not visited again.
+    fn captures(
+        &self,
+        text: &[char],
+        from: usize,
+        visited: &mut [u64],
+    ) -> Option<Vec<Option<usize>>> {
+        enum Job {
+            Explore(usize, usize),
+            Restore(usize, Option<usize>),
+        }
+        let is_word = |pos: usize| text.get(pos).is_some_and(|c| Perl::Word.matches(*c));
+        let insts = self.prog.len();
+        let mut caps = vec![None; self.groups * 2];
+        let mut jobs = vec![Job::Explore(0, from)];
+        while let Some(job) = jobs.pop() {
+            let (mut pc, mut pos) = match job {
+                Job::Explore(pc, pos) => (pc, pos),
+                Job::Restore(slot, value) => {
+                    caps[slot] = value;
+                    continue;
+                }
+            };
+            loop {
+                let state = pos * insts + pc;
+                if visited[state / 64] & (1 << (state % 64)) != 0 {
+                    break;
+                }
+                visited[state / 64] |= 1 << (state % 64);
+                let step = match &self.prog[pc] {
+                    Inst::Char(c) => text.get(pos) == Some(c),
+                    Inst::Any => text.get(pos).is_some_and(|c| *c != '\n'),
+                    Inst::AnyOrNewline => pos < text.len(),
+                    Inst::Class(class) => text.get(pos).is_some_and(|c| class.matches(*c)),
+                    Inst::Start => {
+                        if pos != 0 {
+                            break;
+                        }
+                        pc += 1;
+                        continue;
+                    }
+                    Inst::End => {
+                        if pos != text.len() {
+                            break;
+                        }
+                        pc += 1;
+                        continue;
+                    }
+                    Inst::Boundary(word) => {
+                        let before = pos > 0 && is_word(pos - 1);
+                        if (before != is_word(pos)) != *word {
+                            break;
+                        }
+                        pc += 1;
+                        continue;
+                    }
+                    Inst::Split(first, second) => {
+                        jobs.push(Job::Explore(*second, pos));
+                        pc = *first;
+                        continue;
+                    }
+                    Inst::Jump(target) => {
+                        pc = *target;
+                        continue;
+                    }
+                    Inst::Save(slot) => {
+                        jobs.push(Job::Restore(*slot, caps[*slot]));
+                        caps[*slot] = Some(pos);
+                        pc += 1;
+                        continue;
+                    }
+                    Inst::Match => return Some(caps),
+                };
+                if !step {
+                    break;
+                }
+                pc += 1;
+                pos += 1;
+            }
+        }
+        None
+    }
+
+    /// Captures of every match that does not overlap an earlier one.
+    fn matches<'a>(
+        &'a self,
+        text: &'a [char],
+    ) -> Result<impl Iterator<Item = Vec<Option<usize>>> + 'a, CodeExecError> {
+        let mut visited = self.visited(text)?;
+        let insts = self.prog.len();
+        let mut from = Some(0);
+        Ok(std::iter::from_fn(move || {
+            let pos = from?;
+            // States visited before failed and would fail again, except those
+            // on the way to the last match, which end where this search
+            // starts.
+            for state in pos * insts..(pos + 1) * insts {
+                visited[state / 64] &= !(1 << (state % 64));
+            }
+            let caps = self.captures(text, pos, &mut visited)?;
+            let (start, end) = (caps[0]?, caps[1]?);
+            // An empty match moves on by a character to not find itself again.
+            from = match end > start {
+                true => Some(end),
+                false if end < text.len() => Some(end + 1),
+                false => None,
+            };
+            Some(caps)
+        }))
+    }
+
+    fn group(text: &[char], caps: &[Option<usize>], idx: usize) -> Option<String> {
+        let (start, end) = (caps[idx * 2]?, caps[idx * 2 + 1]?);
+        Some(text[start..end].iter().collect())
+    }
+}
+
+/// Part of a replacement.
+enum Piece {
+    Text(String),
+    Group(usize),
+}
+
+impl Piece {
+    fn parse(regex: &Regex, replacement: &str) -> Result<Vec<Piece>, CodeExecError> {
+        let error = |desc: String| {
+            CodeExecError::new_str(format!("Invalid replacement {:?}: {}", replacement, desc))
+        };
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = replacement.chars().peekable();
+        while let Some(c) = chars.next() {
+            let name = match chars.peek() {
+                _ if c != '$' => None,
+                Some('$') => {
+                    chars.next();
+                    None
+                }
+                Some('{') => {
+                    chars.next();
+                    let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                    Some(name)
+                }
+                Some(c) if c.is_ascii_digit() => {
+                    let mut name = String::new();
+                    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                        name.push(c);
+                    }
+                    Some(name)
+                }
+                _ => None,
+            };
+            let Some(name) = name else {
+                text.push(c);
+                continue;
+            };
+            let idx = match name.parse::<usize>() {
+                Ok(idx) if idx < regex.groups => idx,
+                Ok(_) => return Err(error(format!("no group {}", name))),
+                Err(_) => match regex.names.iter().find(|(other, _)| *other == name) {
+                    Some((_, idx)) => *idx,
+                    None => return Err(error(format!("no group named {:?}", name))),
+                },
+            };
+            pieces.push(Piece::Text(std::mem::take(&mut text)));
+            pieces.push(Piece::Group(idx));
+        }
+        pieces.push(Piece::Text(text));
+        Ok(pieces)
+    }
+}
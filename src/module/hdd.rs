@@ -0,0 +1,151 @@
+use std::{fs, io::Write, rc::Rc};
+
+use crate::{
+    data::node::NativeNode,
+    data::{
+        context::ContextRc,
+        data::{MemData, Mess, Object},
+        module::ModuleFactoryManager,
+        variable::VarType,
+    },
+    statement::CodeExecError,
+};
+
+use super::{IModule, Module, NativeModule};
+
+pub struct HddModule {
+    rd: VarType,
+    rl: VarType,
+    wr: VarType,
+    ap: VarType,
+    ex: VarType,
+    ls: VarType,
+}
+
+impl HddModule {
+    pub const NAME: &str = "hdd";
+
+    fn string_arg<'a>(
+        name: &str,
+        args: &'a [VarType],
+        idx: usize,
+    ) -> Result<&'a str, CodeExecError> {
+        match args.get(idx) {
+            Some(VarType::String(s)) => Ok(s),
+            arg => Err(CodeExecError::new_str(format!(
+                "Argument {} to {}@hdd must be a string, got {:?}",
+                idx + 1,
+                name,
+                arg.unwrap_or(&VarType::Nzero)
+            ))),
+        }
+    }
+
+    fn arg<'a>(name: &str, args: &'a [VarType], idx: usize) -> Result<&'a VarType, CodeExecError> {
+        args.get(idx).ok_or_else(|| {
+            CodeExecError::new_str(format!("Missing argument {} to {}@hdd", idx + 1, name))
+        })
+    }
+
+    fn new_list(ctx: &ContextRc, items: impl IntoIterator<Item = VarType>) -> VarType {
+        let rc = MemData::new_rc(MemData::Object(Object::list(items)));
+        ctx.borrow().get_global().borrow_mut().register_object(&rc);
+        VarType::Ref(rc)
+    }
+
+    /// Whole content of a file, or `N0` if it cannot be read.
+    fn read(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let path = Self::string_arg("rd", args, 0)?;
+        Ok(fs::read_to_string(path)
+            .map(VarType::String)
+            .unwrap_or(VarType::Nzero))
+    }
+
+    /// Lines of a file as a list, or `N0` if it cannot be read.
+    fn read_lines(ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let path = Self::string_arg("rl", args, 0)?;
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                let lines = content.lines().map(|line| VarType::String(line.to_owned()));
+                Ok(Self::new_list(ctx, lines))
+            }
+            Err(_) => Ok(VarType::Nzero),
+        }
+    }
+
+    fn write(args: &[VarType], name: &str, append: bool) -> Result<VarType, CodeExecError> {
+        let path = Self::string_arg(name, args, 0)?;
+        let content = Self::arg(name, args, 1)?.to_string();
+        fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|e| CodeExecError::new_str(format!("Cannot write {}: {}", path, e)))?;
+        Ok(VarType::Nzero)
+    }
+
+    fn exists(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let path = Self::string_arg("ex", args, 0)?;
+        Ok(VarType::Bool(std::path::Path::new(path).exists()))
+    }
+
+    /// Sorted names of the entries of a directory as a list, or `N0` if it
+    /// cannot be read.
+    fn list_dir(ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let path = Self::string_arg("ls", args, 0)?;
+        let Ok(entries) = fs::read_dir(path) else {
+            return Ok(VarType::Nzero);
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        Ok(Self::new_list(ctx, names.into_iter().map(VarType::String)))
+    }
+
+    pub fn new(parent: &ContextRc) -> HddModule {
+        HddModule {
+            rd: NativeNode::as_vartype(parent, Self::read),
+            rl: NativeNode::as_vartype(parent, Self::read_lines),
+            wr: NativeNode::as_vartype(parent, |_: &ContextRc, args: &Vec<VarType>| {
+                Self::write(args, "wr", false)
+            }),
+            ap: NativeNode::as_vartype(parent, |_: &ContextRc, args: &Vec<VarType>| {
+                Self::write(args, "ap", true)
+            }),
+            ex: NativeNode::as_vartype(parent, Self::exists),
+            ls: NativeNode::as_vartype(parent, Self::list_dir),
+        }
+    }
+
+    pub fn register(manager: &mut ModuleFactoryManager) {
+        manager.add_factory(
+            HddModule::NAME,
+            Rc::new(move |parent: &ContextRc| -> Module {
+                Module::Native(NativeModule::new(
+                    HddModule::NAME,
+                    HddModule::NAME,
+                    parent,
+                    Box::new(HddModule::new(parent)),
+                ))
+            }),
+        );
+    }
+}
+
+impl IModule for HddModule {
+    fn exec(&self, _ctx: &ContextRc) -> Result<Mess, CodeExecError> {
+        let mut mess = Mess::new();
+        mess.set("rd", self.rd.clone());
+        mess.set("rl", self.rl.clone());
+        mess.set("wr", self.wr.clone());
+        mess.set("ap", self.ap.clone());
+        mess.set("ex", self.ex.clone());
+        mess.set("ls", self.ls.clone());
+        Ok(mess)
+    }
+}
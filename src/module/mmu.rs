@@ -0,0 +1,78 @@
+use std::rc::Rc;
+
+use crate::{
+    data::node::NativeNode,
+    data::{
+        context::ContextRc,
+        data::{MemData, Mess, Object},
+        module::ModuleFactoryManager,
+        variable::VarType,
+    },
+    statement::CodeExecError,
+};
+
+use super::{IModule, Module, NativeModule};
+
+pub struct MmuModule {
+    gc: VarType,
+    stats: VarType,
+}
+
+impl MmuModule {
+    pub const NAME: &str = "mmu";
+
+    fn count(n: usize) -> VarType {
+        VarType::Int(i64::try_from(n).unwrap_or(i64::MAX))
+    }
+
+    /// Collects garbage now and returns how many objects, nodes and scopes
+    /// were freed.
+    fn gc(ctx: &ContextRc, _args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let global = ctx.borrow().get_global();
+        let freed = global.borrow_mut().collect();
+        Ok(Self::count(freed))
+    }
+
+    /// Counters of the garbage collector as a `lol` object.
+    fn stats(ctx: &ContextRc, _args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let global = ctx.borrow().get_global();
+        let stats = global.borrow().gc_stats;
+        let mut obj = Object::new();
+        obj.set("collections", Self::count(stats.collections));
+        obj.set("freed", Self::count(stats.freed));
+        obj.set("tracked", Self::count(stats.tracked));
+        let rc = MemData::new_rc(MemData::Object(obj));
+        global.borrow_mut().register_object(&rc);
+        Ok(VarType::Ref(rc))
+    }
+
+    pub fn new(parent: &ContextRc) -> MmuModule {
+        MmuModule {
+            gc: NativeNode::as_vartype(parent, Self::gc),
+            stats: NativeNode::as_vartype(parent, Self::stats),
+        }
+    }
+
+    pub fn register(manager: &mut ModuleFactoryManager) {
+        manager.add_factory(
+            MmuModule::NAME,
+            Rc::new(move |parent: &ContextRc| -> Module {
+                Module::Native(NativeModule::new(
+                    MmuModule::NAME,
+                    MmuModule::NAME,
+                    parent,
+                    Box::new(MmuModule::new(parent)),
+                ))
+            }),
+        );
+    }
+}
+
+impl IModule for MmuModule {
+    fn exec(&self, _ctx: &ContextRc) -> Result<Mess, CodeExecError> {
+        let mut mess = Mess::new();
+        mess.set("gc", self.gc.clone());
+        mess.set("stats", self.stats.clone());
+        Ok(mess)
+    }
+}
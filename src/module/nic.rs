@@ -0,0 +1,490 @@
+use std::{iter::Peekable, rc::Rc, str::Chars};
+
+use num_bigint::BigInt;
+
+use crate::{
+    data::node::NativeNode,
+    data::{
+        context::ContextRc,
+        data::{MemData, MemDataRc, Mess, Object, LEN_KEY},
+        module::ModuleFactoryManager,
+        variable::VarType,
+    },
+    statement::CodeExecError,
+};
+
+use super::{IModule, Module, NativeModule};
+
+/// Converts between JSON text and lIIl values. JSON arrays become lists in the
+/// layout of `ll`, and such lists are encoded back as arrays.
+pub struct NicModule {
+    de: VarType,
+    en: VarType,
+}
+
+impl NicModule {
+    pub const NAME: &str = "nic";
+
+    fn decode(ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        match args.first() {
+            Some(VarType::String(text)) => Decoder::new(ctx, text).decode(),
+            arg => Err(CodeExecError::new_str(format!(
+                "de@nic expects a string, got {:?}",
+                arg.unwrap_or(&VarType::Nzero)
+            ))),
+        }
+    }
+
+    /// `value | pretty >> en@nic.` encodes `value`, indented if `pretty`.
+    fn encode(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let value = args.first().unwrap_or(&VarType::Nzero);
+        let pretty = args.get(1).map(bool::from).unwrap_or(false);
+        let mut encoder = Encoder {
+            out: String::new(),
+            pretty,
+            depth: 0,
+            path: Vec::new(),
+        };
+        encoder.value(value)?;
+        Ok(VarType::String(encoder.out))
+    }
+
+    pub fn new(parent: &ContextRc) -> NicModule {
+        NicModule {
+            de: NativeNode::as_vartype(parent, Self::decode),
+            en: NativeNode::as_vartype(parent, Self::encode),
+        }
+    }
+
+    pub fn register(manager: &mut ModuleFactoryManager) {
+        manager.add_factory(
+            NicModule::NAME,
+            Rc::new(move |parent: &ContextRc| -> Module {
+                Module::Native(NativeModule::new(
+                    NicModule::NAME,
+                    NicModule::NAME,
+                    parent,
+                    Box::new(NicModule::new(parent)),
+                ))
+            }),
+        );
+    }
+}
+
+impl IModule for NicModule {
+    fn exec(&self, _ctx: &ContextRc) -> Result<Mess, CodeExecError> {
+        let mut mess = Mess::new();
+        mess.set("de", self.de.clone());
+        mess.set("en", self.en.clone());
+        Ok(mess)
+    }
+}
+
+struct Decoder<'a> {
+    ctx: &'a ContextRc,
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    col: usize,
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    /// Deeper nesting is rejected rather than risking the native stack.
+    const MAX_DEPTH: usize = 512;
+
+    fn new(ctx: &'a ContextRc, text: &'a str) -> Self {
+        Decoder {
+            ctx,
+            chars: text.chars().peekable(),
+            line: 1,
+            col: 1,
+            depth: 0,
+        }
+    }
+
+    fn decode(mut self) -> Result<VarType, CodeExecError> {
+        let value = self.value()?;
+        self.skip_whitespace();
+        match self.chars.peek() {
+            None => Ok(value),
+            Some(_) => Err(self.error("trailing characters")),
+        }
+    }
+
+    fn error(&self, desc: &str) -> CodeExecError {
+        CodeExecError::new_str(format!(
+            "Invalid JSON at {}:{}: {}",
+            self.line, self.col, desc
+        ))
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.chars.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), CodeExecError> {
+        self.skip_whitespace();
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(&format!("expected '{}', found '{}'", expected, c))),
+            None => Err(self.error(&format!("expected '{}', found end of input", expected))),
+        }
+    }
+
+    fn new_object(&self, obj: Object) -> VarType {
+        let rc = MemData::new_rc(MemData::Object(obj));
+        self.ctx
+            .borrow()
+            .get_global()
+            .borrow_mut()
+            .register_object(&rc);
+        VarType::Ref(rc)
+    }
+
+    fn value(&mut self) -> Result<VarType, CodeExecError> {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some('{') => self.nested(Decoder::object),
+            Some('[') => self.nested(Decoder::array),
+            Some('"') => Ok(VarType::String(self.string()?)),
+            Some('-' | '0'..='9') => self.number(),
+            Some('a'..='z') => self.literal(),
+            Some(c) => Err(self.error(&format!("unexpected '{}'", c))),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<VarType, CodeExecError>,
+    ) -> Result<VarType, CodeExecError> {
+        if self.depth >= Decoder::MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    /// Calls `item` for each comma separated item up to `close`.
+    fn items(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<(), CodeExecError>,
+    ) -> Result<(), CodeExecError> {
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&close) {
+            self.next();
+            return Ok(());
+        }
+        loop {
+            item(self)?;
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(c) if c == close => return Ok(()),
+                Some(c) => {
+                    return Err(self.error(&format!("expected ',' or '{}', found '{}'", close, c)))
+                }
+                None => return Err(self.error("unexpected end of input")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<VarType, CodeExecError> {
+        self.expect('{')?;
+        let mut obj = Object::new();
+        self.items('}', |this| {
+            this.skip_whitespace();
+            if this.chars.peek() != Some(&'"') {
+                return Err(this.error("expected a string key"));
+            }
+            let key = this.string()?;
+            this.expect(':')?;
+            let value = this.value()?;
+            obj.set(&key, value);
+            Ok(())
+        })?;
+        Ok(self.new_object(obj))
+    }
+
+    fn array(&mut self) -> Result<VarType, CodeExecError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.items(']', |this| {
+            items.push(this.value()?);
+            Ok(())
+        })?;
+        Ok(self.new_object(Object::list(items)))
+    }
+
+    fn string(&mut self) -> Result<String, CodeExecError> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    s.push(c);
+                }
+                Some(c) if c < ' ' => return Err(self.error("control character in string")),
+                Some(c) => s.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, CodeExecError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.next().and_then(|c| c.to_digit(16));
+            code = code * 16 + digit.ok_or_else(|| self.error("invalid \\u escape"))?;
+        }
+        Ok(code)
+    }
+
+    /// Decodes the digits of a `\u` escape, joining surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char, CodeExecError> {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if self.next() != Some('\\') || self.next() != Some('u') {
+                return Err(self.error("unpaired surrogate"));
+            }
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate"))
+    }
+
+    fn number(&mut self) -> Result<VarType, CodeExecError> {
+        let mut text = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9') {
+                break;
+            }
+            text.push(c);
+            self.next();
+        }
+        let is_int = !text.contains(['.', 'e', 'E']);
+        if is_int {
+            if let Ok(value) = text.parse::<BigInt>() {
+                return Ok(VarType::from(value));
+            }
+        }
+        match text.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(VarType::Float(value)),
+            _ => Err(self.error(&format!("invalid number {}", text))),
+        }
+    }
+
+    fn literal(&mut self) -> Result<VarType, CodeExecError> {
+        let mut word = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !c.is_ascii_lowercase() {
+                break;
+            }
+            word.push(c);
+            self.next();
+        }
+        match word.as_str() {
+            "true" => Ok(VarType::Bool(true)),
+            "false" => Ok(VarType::Bool(false)),
+            "null" => Ok(VarType::Nzero),
+            _ => Err(self.error(&format!("unexpected '{}'", word))),
+        }
+    }
+}
+
+struct Encoder {
+    out: String,
+    pretty: bool,
+    depth: usize,
+    /// Objects being encoded, outermost first, to detect cycles.
+    path: Vec<MemDataRc>,
+}
+
+impl Encoder {
+    const INDENT: &'static str = "  ";
+    /// The same as for decoding, so that whatever is encoded decodes again.
+    const MAX_DEPTH: usize = Decoder::MAX_DEPTH;
+
+    fn newline(&mut self) {
+        if self.pretty {
+            self.out.push('\n');
+            for _ in 0..self.depth {
+                self.out.push_str(Encoder::INDENT);
+            }
+        }
+    }
+
+    fn value(&mut self, value: &VarType) -> Result<(), CodeExecError> {
+        match value {
+            VarType::Nzero => self.out.push_str("null"),
+            VarType::Bool(value) => self.out.push_str(if *value { "true" } else { "false" }),
+            VarType::Int(value) => self.out.push_str(&value.to_string()),
+            VarType::BigInt(value) => self.out.push_str(&value.to_string()),
+            VarType::Float(value) => {
+                if !value.is_finite() {
+                    return Err(CodeExecError::new_str(format!(
+                        "Cannot encode {} as JSON",
+                        value
+                    )));
+                }
+                self.out.push_str(&format!("{:?}", value));
+            }
+            VarType::String(value) => self.string(value),
+            VarType::Tuple(tuple) => self.array(&tuple.items)?,
+            VarType::Ref(data) => self.reference(data)?,
+        }
+        Ok(())
+    }
+
+    fn string(&mut self, s: &str) {
+        self.out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                c if c < ' ' => self.out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+
+    /// Writes comma separated items between `open` and `close`.
+    fn items<T>(
+        &mut self,
+        open: char,
+        close: char,
+        items: &[T],
+        mut item: impl FnMut(&mut Self, &T) -> Result<(), CodeExecError>,
+    ) -> Result<(), CodeExecError> {
+        self.out.push(open);
+        if !items.is_empty() {
+            if self.depth >= Encoder::MAX_DEPTH {
+                return Err(CodeExecError::new_str(format!(
+                    "Cannot encode values nested more than {} deep as JSON",
+                    Encoder::MAX_DEPTH
+                )));
+            }
+            self.depth += 1;
+            for (idx, value) in items.iter().enumerate() {
+                if idx > 0 {
+                    self.out.push(',');
+                }
+                self.newline();
+                item(self, value)?;
+            }
+            self.depth -= 1;
+            self.newline();
+        }
+        self.out.push(close);
+        Ok(())
+    }
+
+    fn array(&mut self, items: &[VarType]) -> Result<(), CodeExecError> {
+        self.items('[', ']', items, |this, value| this.value(value))
+    }
+
+    fn reference(&mut self, data: &MemDataRc) -> Result<(), CodeExecError> {
+        if self.path.iter().any(|rc| Rc::ptr_eq(rc, data)) {
+            return Err(CodeExecError::new_str(
+                "Cannot encode a reference cycle as JSON".to_owned(),
+            ));
+        }
+        let obj = match &*data.borrow() {
+            MemData::Object(obj) => obj.clone(),
+            MemData::Mess(_) => {
+                return Err(CodeExecError::new_str(
+                    "Cannot encode a module as JSON".to_owned(),
+                ))
+            }
+            MemData::Node(_) => {
+                return Err(CodeExecError::new_str(
+                    "Cannot encode a node as JSON".to_owned(),
+                ))
+            }
+        };
+        self.path.push(data.clone());
+        let result = match Encoder::list_items(&obj) {
+            Some(items) => self.array(&items),
+            None => self.object(&obj),
+        };
+        self.path.pop();
+        result
+    }
+
+    /// Items of `obj` if it is a list: `len` and the keys below it are its
+    /// only members that are not `N0`.
+    fn list_items(obj: &Object) -> Option<Vec<VarType>> {
+        let Some(VarType::Int(len)) = obj.get(LEN_KEY) else {
+            return None;
+        };
+        let len = usize::try_from(len).ok()?;
+        let members = obj
+            .keys()
+            .iter()
+            .filter(|key| !matches!(obj.get(key), Some(VarType::Nzero)))
+            .count();
+        let items: Vec<VarType> = (0..len)
+            .map(|idx| obj.get(&idx.to_string()).unwrap_or(VarType::Nzero))
+            .collect();
+        let set_items = items
+            .iter()
+            .filter(|item| !matches!(item, VarType::Nzero))
+            .count();
+        (members == set_items + 1).then_some(items)
+    }
+
+    fn object(&mut self, obj: &Object) -> Result<(), CodeExecError> {
+        let members: Vec<(String, VarType)> = obj
+            .keys()
+            .into_iter()
+            .filter_map(|key| match obj.get(&key) {
+                Some(VarType::Nzero) | None => None,
+                Some(value) => Some((key, value)),
+            })
+            .collect();
+        let separator = if self.pretty { ": " } else { ":" };
+        self.items('{', '}', &members, |this, (key, value)| {
+            this.string(key);
+            this.out.push_str(separator);
+            this.value(value)
+        })
+    }
+}
@@ -0,0 +1,118 @@
+use std::rc::Rc;
+
+use crate::{
+    data::node::{NativeFnPtr, NativeNode},
+    data::{
+        context::ContextRc,
+        data::{MemData, MemDataRc, Mess, Object},
+        module::ModuleFactoryManager,
+        variable::VarType,
+    },
+    expr::MemberExpr,
+    statement::CodeExecError,
+};
+
+use super::{IModule, Module, NativeModule};
+
+/// Operations on the keys of `lol` objects that member access cannot express.
+/// A key set to `N0` is still there until it is deleted.
+pub struct RamModule {
+    members: Vec<(&'static str, VarType)>,
+}
+
+impl RamModule {
+    pub const NAME: &str = "ram";
+
+    /// The object given as the first argument to `name@ram`.
+    fn object(name: &str, args: &[VarType]) -> Result<MemDataRc, CodeExecError> {
+        if let Some(VarType::Ref(data)) = args.first() {
+            if let MemData::Object(_) = &*data.borrow() {
+                return Ok(data.clone());
+            }
+        }
+        Err(CodeExecError::new_str(format!(
+            "Argument 1 to {}@ram must be a lol, got {:?}",
+            name,
+            args.first().unwrap_or(&VarType::Nzero)
+        )))
+    }
+
+    /// The key given as the second argument to `name@ram`, converted like the
+    /// key of a member access.
+    fn key(name: &str, args: &[VarType]) -> Result<String, CodeExecError> {
+        match args.get(1) {
+            Some(key) => Ok(MemberExpr::key_string(key.clone())),
+            None => Err(CodeExecError::new_str(format!(
+                "{}@ram requires a key",
+                name
+            ))),
+        }
+    }
+
+    fn with_object<T>(data: &MemDataRc, f: impl FnOnce(&mut Object) -> T) -> T {
+        match &mut *data.borrow_mut() {
+            MemData::Object(obj) => f(obj),
+            _ => unreachable!("checked by RamModule::object"),
+        }
+    }
+
+    /// `obj | key >> del@ram.` Removes `key` from `obj`, returning whether it
+    /// was there.
+    fn del(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let data = RamModule::object("del", args)?;
+        let key = RamModule::key("del", args)?;
+        // The value is dropped only after the object is no longer borrowed.
+        let removed = RamModule::with_object(&data, |obj| obj.remove(&key));
+        Ok(VarType::Bool(removed.is_some()))
+    }
+
+    /// `obj | key >> has@ram.` Whether `obj` has `key`, even if set to `N0`.
+    fn has(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let data = RamModule::object("has", args)?;
+        let key = RamModule::key("has", args)?;
+        let has = RamModule::with_object(&data, |obj| obj.has(&key));
+        Ok(VarType::Bool(has))
+    }
+
+    /// `obj >> cnt@ram.` Number of keys of `obj`.
+    fn cnt(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let data = RamModule::object("cnt", args)?;
+        let count = RamModule::with_object(&data, |obj| obj.count());
+        Ok(VarType::Int(count as i64))
+    }
+
+    pub fn new(parent: &ContextRc) -> RamModule {
+        let node = |func: NativeFnPtr| NativeNode::as_vartype(parent, func);
+        RamModule {
+            members: vec![
+                ("del", node(Self::del)),
+                ("has", node(Self::has)),
+                ("cnt", node(Self::cnt)),
+            ],
+        }
+    }
+
+    pub fn register(manager: &mut ModuleFactoryManager) {
+        manager.add_factory(
+            RamModule::NAME,
+            Rc::new(move |parent: &ContextRc| -> Module {
+                Module::Native(NativeModule::new(
+                    RamModule::NAME,
+                    RamModule::NAME,
+                    parent,
+                    Box::new(RamModule::new(parent)),
+                ))
+            }),
+        );
+    }
+}
+
+impl IModule for RamModule {
+    fn exec(&self, _ctx: &ContextRc) -> Result<Mess, CodeExecError> {
+        let mut mess = Mess::new();
+        for (name, value) in &self.members {
+            mess.set(name, value.clone());
+        }
+        Ok(mess)
+    }
+}
@@ -0,0 +1,325 @@
+use std::rc::Rc;
+
+use crate::{
+    data::node::{NativeFnPtr, NativeNode},
+    data::{
+        context::ContextRc,
+        data::{MemData, Mess, LEN_KEY},
+        module::ModuleFactoryManager,
+        variable::VarType,
+    },
+    statement::CodeExecError,
+};
+
+use super::{IModule, Module, NativeModule};
+
+/// String functions. Indices and lengths count characters, not bytes.
+pub struct SsdModule {
+    members: Vec<(&'static str, VarType)>,
+}
+
+impl SsdModule {
+    pub const NAME: &str = "ssd";
+    /// Longest string, in bytes, that `rpt@ssd` and `join@ssd` build.
+    const MAX_LEN: usize = 1 << 30;
+
+    fn arg<'a>(name: &str, args: &'a [VarType], idx: usize) -> Result<&'a VarType, CodeExecError> {
+        args.get(idx).ok_or_else(|| {
+            CodeExecError::new_str(format!(
+                "{}@ssd requires {} arguments, got {}",
+                name,
+                idx + 1,
+                args.len()
+            ))
+        })
+    }
+
+    fn too_long(name: &str) -> CodeExecError {
+        CodeExecError::new_str(format!(
+            "{}@ssd would make a string longer than {} bytes",
+            name,
+            SsdModule::MAX_LEN
+        ))
+    }
+
+    fn string_arg<'a>(
+        name: &str,
+        args: &'a [VarType],
+        idx: usize,
+    ) -> Result<&'a str, CodeExecError> {
+        match SsdModule::arg(name, args, idx)? {
+            VarType::String(s) => Ok(s),
+            arg => Err(CodeExecError::new_str(format!(
+                "Argument {} to {}@ssd must be a string, got {:?}",
+                idx + 1,
+                name,
+                arg
+            ))),
+        }
+    }
+
+    fn int_arg(name: &str, args: &[VarType], idx: usize) -> Result<i64, CodeExecError> {
+        match SsdModule::arg(name, args, idx)? {
+            VarType::Int(value) => Ok(*value),
+            arg => Err(CodeExecError::new_str(format!(
+                "Argument {} to {}@ssd must be an integer, got {:?}",
+                idx + 1,
+                name,
+                arg
+            ))),
+        }
+    }
+
+    /// Optional integer argument, absent if missing or `N0`.
+    fn opt_int_arg(name: &str, args: &[VarType], idx: usize) -> Result<Option<i64>, CodeExecError> {
+        match args.get(idx) {
+            None | Some(VarType::Nzero) => Ok(None),
+            Some(_) => SsdModule::int_arg(name, args, idx).map(Some),
+        }
+    }
+
+    /// Turns a possibly negative index, counting from the end, into one in
+    /// `0..=len`.
+    fn clamp(idx: i64, len: usize) -> usize {
+        let idx = if idx < 0 { idx + len as i64 } else { idx };
+        idx.clamp(0, len as i64) as usize
+    }
+
+    fn map(
+        name: &'static str,
+        args: &[VarType],
+        f: fn(&str) -> String,
+    ) -> Result<VarType, CodeExecError> {
+        Ok(VarType::String(f(SsdModule::string_arg(name, args, 0)?)))
+    }
+
+    fn test(
+        name: &'static str,
+        args: &[VarType],
+        f: fn(&str, &str) -> bool,
+    ) -> Result<VarType, CodeExecError> {
+        let s = SsdModule::string_arg(name, args, 0)?;
+        let other = SsdModule::string_arg(name, args, 1)?;
+        Ok(VarType::Bool(f(s, other)))
+    }
+
+    fn len(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let s = SsdModule::string_arg("len", args, 0)?;
+        Ok(VarType::Int(s.chars().count() as i64))
+    }
+
+    /// `s | start | end >> sub@ssd.` Characters from `start` up to `end`, which
+    /// defaults to the end of `s`. Negative indices count from the end.
+    fn sub(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let s = SsdModule::string_arg("sub", args, 0)?;
+        let len = s.chars().count();
+        let start = SsdModule::clamp(SsdModule::int_arg("sub", args, 1)?, len);
+        let end = match SsdModule::opt_int_arg("sub", args, 2)? {
+            Some(end) => SsdModule::clamp(end, len),
+            None => len,
+        };
+        let sub = s.chars().skip(start).take(end.saturating_sub(start));
+        Ok(VarType::String(sub.collect()))
+    }
+
+    /// `s | needle | from >> find@ssd.` Index of the first `needle` at or after
+    /// `from`, or `N0`.
+    fn find(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let s = SsdModule::string_arg("find", args, 0)?;
+        let needle = SsdModule::string_arg("find", args, 1)?;
+        let from = SsdModule::opt_int_arg("find", args, 2)?.unwrap_or(0);
+        let from = SsdModule::clamp(from, s.chars().count());
+        let offset = s.char_indices().nth(from).map_or(s.len(), |(i, _)| i);
+        Ok(match s[offset..].find(needle) {
+            Some(i) => VarType::Int((from + s[offset..offset + i].chars().count()) as i64),
+            None => VarType::Nzero,
+        })
+    }
+
+    fn replace(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let s = SsdModule::string_arg("rep", args, 0)?;
+        let from = SsdModule::string_arg("rep", args, 1)?;
+        let to = SsdModule::string_arg("rep", args, 2)?;
+        if from.is_empty() {
+            return Ok(VarType::String(s.to_owned()));
+        }
+        Ok(VarType::String(s.replace(from, to)))
+    }
+
+    fn repeat(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let s = SsdModule::string_arg("rpt", args, 0)?;
+        let count = SsdModule::int_arg("rpt", args, 1)?;
+        let count = usize::try_from(count).map_err(|_| {
+            CodeExecError::new_str(format!(
+                "rpt@ssd expects a count of at least 0, got {}",
+                count
+            ))
+        })?;
+        match s.len().checked_mul(count) {
+            Some(len) if len <= SsdModule::MAX_LEN => Ok(VarType::String(s.repeat(count))),
+            _ => Err(SsdModule::too_long("rpt")),
+        }
+    }
+
+    /// `items | sep >> join@ssd.` Joins the items of an `ll` list or a tuple
+    /// with `sep`, which defaults to nothing.
+    fn join(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let items = SsdModule::arg("join", args, 0)?;
+        let sep = match args.get(1) {
+            Some(VarType::Nzero) | None => "",
+            Some(_) => SsdModule::string_arg("join", args, 1)?,
+        };
+        let mut joined = String::new();
+        let mut push = |i: i64, item: &VarType| {
+            let item = item.to_string();
+            let sep = if i > 0 { sep } else { "" };
+            if joined.len() + sep.len() + item.len() > SsdModule::MAX_LEN {
+                return Err(SsdModule::too_long("join"));
+            }
+            joined.push_str(sep);
+            joined.push_str(&item);
+            Ok(())
+        };
+        match items {
+            VarType::Tuple(tuple) => {
+                for (i, item) in tuple.items.iter().enumerate() {
+                    push(i as i64, item)?;
+                }
+            }
+            VarType::Ref(data) => {
+                if let MemData::Object(obj) = &*data.borrow() {
+                    let len = match obj.get(LEN_KEY) {
+                        Some(VarType::Int(len)) => len,
+                        _ => 0,
+                    };
+                    // Missing items show as `N0`, so a list this long cannot
+                    // fit anyway.
+                    if len > SsdModule::MAX_LEN as i64 {
+                        return Err(SsdModule::too_long("join"));
+                    }
+                    for i in 0..len {
+                        push(i, &obj.get(&i.to_string()).unwrap_or_default())?;
+                    }
+                }
+            }
+            arg => {
+                return Err(CodeExecError::new_str(format!(
+                    "join@ssd expects a list or a tuple, got {:?}",
+                    arg
+                )))
+            }
+        }
+        Ok(VarType::String(joined))
+    }
+
+    /// `s | idx >> ord@ssd.` Code of the character at `idx`, the first one by
+    /// default, or `N0` if there is none.
+    fn ord(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let s = SsdModule::string_arg("ord", args, 0)?;
+        let idx = SsdModule::opt_int_arg("ord", args, 1)?.unwrap_or(0);
+        let c = usize::try_from(idx).ok().and_then(|idx| s.chars().nth(idx));
+        Ok(c.map_or(VarType::Nzero, |c| VarType::Int(c as i64)))
+    }
+
+    /// The character with the given code, or `N0` if there is none.
+    fn chr(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let code = SsdModule::int_arg("chr", args, 0)?;
+        let c = u32::try_from(code).ok().and_then(char::from_u32);
+        Ok(c.map_or(VarType::Nzero, |c| VarType::String(c.to_string())))
+    }
+
+    /// `template | args >> fmt@ssd.` Replaces each `{}` in `template` with the
+    /// next argument. `{{` and `}}` stand for braces.
+    fn format(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let template = SsdModule::string_arg("fmt", args, 0)?;
+        let mut values = args[1..].iter();
+        let mut result = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    chars.next();
+                    result.push(c);
+                }
+                ('{', Some('}')) => {
+                    chars.next();
+                    let value = values.next().ok_or_else(|| {
+                        CodeExecError::new_str(format!(
+                            "fmt@ssd needs more than {} arguments for {:?}",
+                            args.len() - 1,
+                            template
+                        ))
+                    })?;
+                    result.push_str(&value.to_string());
+                }
+                _ => result.push(c),
+            }
+        }
+        Ok(VarType::String(result))
+    }
+
+    pub fn new(parent: &ContextRc) -> SsdModule {
+        let node = |func: NativeFnPtr| NativeNode::as_vartype(parent, func);
+        SsdModule {
+            members: vec![
+                ("len", node(Self::len)),
+                ("sub", node(Self::sub)),
+                ("find", node(Self::find)),
+                (
+                    "has",
+                    node(|_, args| Self::test("has", args, |s, other| s.contains(other))),
+                ),
+                ("rep", node(Self::replace)),
+                (
+                    "up",
+                    node(|_, args| Self::map("up", args, str::to_uppercase)),
+                ),
+                (
+                    "lo",
+                    node(|_, args| Self::map("lo", args, str::to_lowercase)),
+                ),
+                (
+                    "trim",
+                    node(|_, args| Self::map("trim", args, |s| s.trim().to_owned())),
+                ),
+                (
+                    "sw",
+                    node(|_, args| Self::test("sw", args, |s, other| s.starts_with(other))),
+                ),
+                (
+                    "ew",
+                    node(|_, args| Self::test("ew", args, |s, other| s.ends_with(other))),
+                ),
+                ("rpt", node(Self::repeat)),
+                ("join", node(Self::join)),
+                ("ord", node(Self::ord)),
+                ("chr", node(Self::chr)),
+                ("fmt", node(Self::format)),
+            ],
+        }
+    }
+
+    pub fn register(manager: &mut ModuleFactoryManager) {
+        manager.add_factory(
+            SsdModule::NAME,
+            Rc::new(move |parent: &ContextRc| -> Module {
+                Module::Native(NativeModule::new(
+                    SsdModule::NAME,
+                    SsdModule::NAME,
+                    parent,
+                    Box::new(SsdModule::new(parent)),
+                ))
+            }),
+        );
+    }
+}
+
+impl IModule for SsdModule {
+    fn exec(&self, _ctx: &ContextRc) -> Result<Mess, CodeExecError> {
+        let mut mess = Mess::new();
+        for (name, value) in &self.members {
+            mess.set(name, value.clone());
+        }
+        Ok(mess)
+    }
+}
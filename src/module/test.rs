@@ -1,37 +1,177 @@
 use std::rc::Rc;
 
+use num_traits::ToPrimitive;
+
 use crate::{
-    data::node::NativeNode,
-    data::{context::ContextRc, data::Mess, module::ModuleFactoryManager, variable::VarType},
+    data::node::{NativeNode, Node},
+    data::{
+        context::ContextRc,
+        data::{MemData, Mess},
+        module::ModuleFactoryManager,
+        variable::VarType,
+    },
+    expr::{CompareExpr, CompareOp},
     module::{IModule, Module, NativeModule},
     statement::CodeExecError,
 };
 
 pub struct TestModule {
     assert: VarType,
+    eq: VarType,
+    ne: VarType,
+    approx: VarType,
+    raises: VarType,
+    capture: VarType,
 }
 
 impl TestModule {
     pub const NAME: &str = "ut";
+    const DEFAULT_TOLERANCE: f64 = 1e-9;
 
-    fn assert(args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
-        let is_true: bool = (&args[0]).into();
-        assert!(
-            is_true,
-            "{}",
-            args.iter()
+    fn fail(ctx: &ContextRc, what: String, message: &[VarType]) -> CodeExecError {
+        let mut desc = format!("Assertion failed: {}", what);
+        if !message.is_empty() {
+            let message = message
+                .iter()
                 .map(|item| item.to_string())
                 .collect::<Vec<String>>()
-                .join(" ")
-        );
-        Ok(VarType::Nzero)
+                .join(" ");
+            desc = format!("{} ({})", desc, message);
+        }
+        CodeExecError::new(&ctx.borrow(), desc)
+    }
+
+    fn arg<'a>(name: &str, args: &'a [VarType], idx: usize) -> Result<&'a VarType, CodeExecError> {
+        args.get(idx).ok_or_else(|| {
+            CodeExecError::new_str(format!(
+                "{}@ut requires {} arguments, got {}",
+                name,
+                idx + 1,
+                args.len()
+            ))
+        })
+    }
+
+    fn assert(ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let is_true: bool = TestModule::arg("assert", args, 0)?.into();
+        if is_true {
+            return Ok(VarType::Nzero);
+        }
+        let mut desc = "Assertion failed".to_owned();
+        if args.len() > 1 {
+            let message = args[1..]
+                .iter()
+                .map(|item| item.to_string())
+                .collect::<Vec<String>>()
+                .join(" ");
+            desc = format!("{}: {}", desc, message);
+        }
+        Err(CodeExecError::new(&ctx.borrow(), desc))
+    }
+
+    fn compare(
+        ctx: &ContextRc,
+        args: &Vec<VarType>,
+        op: CompareOp,
+    ) -> Result<VarType, CodeExecError> {
+        let (name, sign) = match op {
+            CompareOp::Equal => ("eq", "!="),
+            _ => ("ne", "="),
+        };
+        let lhs = TestModule::arg(name, args, 0)?;
+        let rhs = TestModule::arg(name, args, 1)?;
+        let result = CompareExpr::apply(ctx, op, lhs.clone(), rhs.clone())?;
+        if bool::from(&result) {
+            return Ok(VarType::Nzero);
+        }
+        let what = format!("{:?} {} {:?}", lhs, sign, rhs);
+        Err(TestModule::fail(ctx, what, &args[2..]))
+    }
+
+    fn as_float(value: &VarType) -> Result<f64, CodeExecError> {
+        match value {
+            VarType::Int(value) => Ok(*value as f64),
+            VarType::BigInt(value) => Ok(value.to_f64().unwrap_or(f64::NAN)),
+            VarType::Float(value) => Ok(*value),
+            _ => Err(CodeExecError::new_str(format!(
+                "approx@ut expects numbers, got {:?}",
+                value
+            ))),
+        }
+    }
+
+    /// `actual | expected | tolerance >> approx@ut.`, the tolerance being
+    /// optional.
+    fn approx(ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let lhs = TestModule::as_float(TestModule::arg("approx", args, 0)?)?;
+        let rhs = TestModule::as_float(TestModule::arg("approx", args, 1)?)?;
+        let tolerance = match args.get(2) {
+            Some(value) => TestModule::as_float(value)?,
+            None => TestModule::DEFAULT_TOLERANCE,
+        };
+        if (lhs - rhs).abs() <= tolerance {
+            return Ok(VarType::Nzero);
+        }
+        let what = format!("{:?} is not within {:?} of {:?}", lhs, tolerance, rhs);
+        Err(TestModule::fail(
+            ctx,
+            what,
+            args.get(3..).unwrap_or_default(),
+        ))
+    }
+
+    fn node(name: &str, args: &[VarType]) -> Result<Node, CodeExecError> {
+        let arg = TestModule::arg(name, args, 0)?;
+        if let VarType::Ref(data) = arg {
+            if let MemData::Node(node) = &*data.borrow() {
+                return Ok(node.clone());
+            }
+        }
+        Err(CodeExecError::new_str(format!(
+            "{}@ut expects a node, got {:?}",
+            name, arg
+        )))
+    }
+
+    /// `node | args >> raises@ut.` calls `node` with `args` and returns the
+    /// description of the error it raised.
+    fn raises(ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let mut node = TestModule::node("raises", args)?;
+        match node.exec(&args[1..].to_vec()) {
+            Ok(value) => {
+                let name = node.name().unwrap_or("node");
+                let what = format!("{} returned {:?} instead of raising", name, value);
+                Err(TestModule::fail(ctx, what, &[]))
+            }
+            Err(e) => Ok(VarType::String(e.desc().to_owned())),
+        }
+    }
+
+    /// `node | args >> capture@ut.` calls `node` with `args` and returns what
+    /// it printed through `cpu` instead of printing it.
+    fn capture(ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
+        let mut node = TestModule::node("capture", args)?;
+        let global = ctx.borrow().get_global();
+        global.borrow_mut().begin_capture();
+        let result = node.exec(&args[1..].to_vec());
+        let output = global.borrow_mut().end_capture();
+        result.map(|_| VarType::String(output))
     }
 
     pub fn new(parent: &ContextRc) -> TestModule {
         TestModule {
-            assert: NativeNode::as_vartype(parent, |_: &ContextRc, args: &Vec<VarType>| {
-                Self::assert(args)
+            assert: NativeNode::as_vartype(parent, |ctx: &ContextRc, args: &Vec<VarType>| {
+                Self::assert(ctx, args)
+            }),
+            eq: NativeNode::as_vartype(parent, |ctx: &ContextRc, args: &Vec<VarType>| {
+                Self::compare(ctx, args, CompareOp::Equal)
+            }),
+            ne: NativeNode::as_vartype(parent, |ctx: &ContextRc, args: &Vec<VarType>| {
+                Self::compare(ctx, args, CompareOp::NotEqual)
             }),
+            approx: NativeNode::as_vartype(parent, Self::approx),
+            raises: NativeNode::as_vartype(parent, Self::raises),
+            capture: NativeNode::as_vartype(parent, Self::capture),
         }
     }
 
@@ -54,6 +194,11 @@ impl IModule for TestModule {
     fn exec(&self, _ctx: &ContextRc) -> Result<Mess, CodeExecError> {
         let mut mess = Mess::new();
         mess.set("assert", self.assert.clone());
+        mess.set("eq", self.eq.clone());
+        mess.set("ne", self.ne.clone());
+        mess.set("approx", self.approx.clone());
+        mess.set("raises", self.raises.clone());
+        mess.set("capture", self.capture.clone());
         Ok(mess)
     }
 }
@@ -1,5 +1,7 @@
 use std::rc::Rc;
 
+use num_bigint::BigInt;
+
 use crate::{
     data::node::NativeNode,
     data::{context::ContextRc, data::Mess, module::ModuleFactoryManager, variable::VarType},
@@ -20,8 +22,8 @@ impl TpModule {
 
     fn parse_int(_ctx: &ContextRc, args: &Vec<VarType>) -> Result<VarType, CodeExecError> {
         if let Some(VarType::String(s)) = args.first() {
-            if let Ok(n) = s.trim().parse::<i64>() {
-                return Ok(VarType::Int(n));
+            if let Ok(n) = s.trim().parse::<BigInt>() {
+                return Ok(VarType::from(n));
             }
         }
         Ok(VarType::Nzero)
@@ -58,13 +60,8 @@ impl TpModule {
             _ => return Err(CodeExecError::new_str("Second argument to tp@ss must be a string".to_string())),
         };
 
-        let parts: Vec<&str> = s.split(|c| delims.contains(c)).collect();
-
-        let mut obj = crate::data::data::Object::new();
-        for (i, part) in parts.iter().enumerate() {
-            obj.set(&i.to_string(), VarType::String(part.to_string()));
-        }
-        obj.set(crate::data::data::LEN_KEY, VarType::Int(parts.len() as i64));
+        let parts = s.split(|c| delims.contains(c));
+        let obj = crate::data::data::Object::list(parts.map(|part| VarType::String(part.to_string())));
 
         let rc = crate::data::data::MemData::new_rc(crate::data::data::MemData::Object(obj));
         ctx.borrow().get_global().borrow_mut().register_object(&rc);
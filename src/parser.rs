@@ -1,6 +1,8 @@
 use std::fs;
 
-use pest::Parser;
+use std::rc::Rc;
+
+use pest::{error::InputLocation, iterators::Pair, Parser};
 use pest_derive::Parser;
 
 use crate::{
@@ -8,6 +10,7 @@ use crate::{
     module::{CodeModule, Module},
     parser::module::parse_module,
     statement::CodeExecError,
+    utils::span::{Source, Span},
 };
 
 pub mod expr;
@@ -20,10 +23,22 @@ pub mod stmt;
 #[allow(non_camel_case_types)]
 struct lIIlParser;
 
+fn span_of(src: &Rc<Source>, pair: &Pair<Rule>) -> Span {
+    let span = pair.as_span();
+    Span::new(src, span.start(), span.end())
+}
+
 pub fn parse(module: &mut CodeModule, input: &str) -> Result<(), CodeExecError> {
-    let pairs = lIIlParser::parse(Rule::module, input)
-        .map_err(|e| CodeExecError::new_str(format!("Syntax error: {:?}", e)))?;
-    parse_module(module, pairs.peek().unwrap().into_inner())
+    let src = Source::new_rc(&module.path.to_string(), input);
+    let pairs = lIIlParser::parse(Rule::module, input).map_err(|e| {
+        let (start, end) = match e.location {
+            InputLocation::Pos(pos) => (pos, pos),
+            InputLocation::Span(span) => span,
+        };
+        CodeExecError::new_str(format!("Syntax error: {}", e.variant.message()))
+            .at(&Span::new(&src, start, end))
+    })?;
+    parse_module(module, &src, pairs.peek().unwrap().into_inner())
 }
 
 pub fn parse_str(
@@ -32,7 +47,8 @@ pub fn parse_str(
     parent: &ContextRc,
 ) -> Result<CodeModule, CodeExecError> {
     let root = parent.borrow().get_root();
-    let mut module = CodeModule::new(name, &Module::builtin_path(name), &root, false);
+    let path = Module::builtin_path(&Module::code_path(name));
+    let mut module = CodeModule::new(name, &path, &root, false);
     Context::with(&root, || {
         parse(&mut module, source)?;
         Ok(module)
@@ -42,11 +58,14 @@ pub fn parse_str(
 pub fn parse_file(file: &str, root_ctx: Option<ContextRc>) -> Result<CodeModule, CodeExecError> {
     let is_root = root_ctx.is_none();
     let context = root_ctx.unwrap_or(Context::root_rc());
-    let abs_file_path = fs::canonicalize(file).unwrap();
-    let abs_file = abs_file_path.to_str().unwrap();
+    let io_error =
+        |e: std::io::Error| CodeExecError::new_str(format!("Cannot read {}: {}", file, e));
+    let abs_file_path = fs::canonicalize(file).map_err(io_error)?;
+    let abs_file = abs_file_path
+        .to_str()
+        .ok_or_else(|| CodeExecError::new_str(format!("Path {} is not valid UTF-8", file)))?;
     // Create context.
-    let input = std::fs::read_to_string(file)
-        .map_err(|e| CodeExecError::new_str(format!("IO error: {:?}", e)))?;
+    let input = std::fs::read_to_string(file).map_err(io_error)?;
     let mut module = CodeModule::new("lIIl", abs_file, &context, is_root);
     // Parse file.
     Context::with(&context, || {
@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use once_cell::sync::Lazy;
 use pest::{
     iterators::Pairs,
@@ -7,13 +9,22 @@ use pest::{
 use crate::{
     data::lvalue::LValue,
     expr::{
-        AddExpr, CompareExpr, CompareOp, DivExpr, Expr, IdentifierExpr, MemberExpr, ModExpr,
-        MulExpr, NegExpr, NodeCallExpr, NotExpr, SubExpr, TupleExpr,
+        AddExpr, CompareExpr, CompareOp, DivExpr, Expr, ExprKind, IdentifierExpr, MemberExpr,
+        ModExpr, MulExpr, NegExpr, NodeCallExpr, NotExpr, SubExpr, TupleExpr,
+    },
+    module::CodeModule,
+    statement::{
+        node_def::NodeDefStatement, ret::ReturnStatement, CodeExecError, Statement, StatementKind,
     },
-    statement::CodeExecError,
+    utils::span::Source,
 };
 
-use super::{literal::parse_literal, Rule};
+use super::{
+    literal::{parse_literal, parse_node_params},
+    span_of,
+    stmt::parse_stmt_block,
+    Rule,
+};
 
 static PRATT_PARSER: Lazy<PrattParser<Rule>> = Lazy::new(|| {
     PrattParser::new()
@@ -35,133 +46,219 @@ static PRATT_PARSER: Lazy<PrattParser<Rule>> = Lazy::new(|| {
         .op(Op::infix(Rule::member_op, Assoc::Right))
 });
 
-pub fn parse_lvalue(pairs: Pairs<Rule>) -> Result<LValue, CodeExecError> {
+pub fn parse_lvalue(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<LValue, CodeExecError> {
     let mut pairs = pairs.into_iter();
     let pair = pairs.next().unwrap();
     match pair.as_rule() {
         Rule::identifier => Ok(LValue::Identifier(pair.as_str().to_string())),
         Rule::member_expr => {
-            let expr = parse_expr(pair.into_inner());
-            match expr {
-                Expr::Member(member) => Ok(LValue::MemberExpr(member)),
+            let expr = parse_expr(module, src, pair.into_inner())?;
+            match expr.kind {
+                ExprKind::Member(member) => Ok(LValue::MemberExpr(member)),
                 _ => Err(CodeExecError::new_str(format!(
                     "Expected member expression, found {:?}",
                     expr
-                ))),
+                ))
+                .at(&expr.span)),
             }
         }
         _ => panic!("parse_lvalue: {:?}", pair),
     }
 }
 
-fn parse_expr_primary(pairs: Pairs<Rule>) -> Expr {
+pub fn parse_lvalue_tuple(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<Vec<LValue>, CodeExecError> {
+    let mut lvalues = Vec::new();
     for pair in pairs {
         match pair.as_rule() {
-            Rule::literal_expr => return parse_literal(pair.into_inner()),
+            Rule::lvalue => lvalues.push(parse_lvalue(module, src, pair.into_inner())?),
+            Rule::tuple_op => continue,
+            _ => panic!("parse_lvalue_tuple: {:?}", pair),
+        }
+    }
+    Ok(lvalues)
+}
+
+/// Parses `(args << body)`. A body that is an expression is returned.
+fn parse_node_expr(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<NodeDefStatement, CodeExecError> {
+    let mut params = (Vec::new(), None);
+    let mut body = None;
+    for pair in pairs {
+        let span = span_of(src, &pair);
+        match pair.as_rule() {
+            Rule::node_params => params = parse_node_params(module, src, pair.into_inner())?,
+            Rule::stmt_block => {
+                let stmts = parse_stmt_block(module, src, pair.into_inner())?;
+                body = Some(Statement::new(StatementKind::Stmts(stmts), span));
+            }
+            Rule::expr => {
+                let value = parse_expr(module, src, pair.into_inner())?;
+                body = Some(Statement::new(
+                    StatementKind::Ret(ReturnStatement { value }),
+                    span,
+                ));
+            }
+            _ => panic!("parse_node_expr: {:?}", pair),
+        }
+    }
+    let (params, rest) = params;
+    Ok(NodeDefStatement {
+        name: NodeDefStatement::ANONYMOUS.to_owned(),
+        params,
+        rest,
+        body: Box::new(body.unwrap()),
+        path: module.path.to_string(),
+    })
+}
+
+fn parse_expr_primary(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<Expr, CodeExecError> {
+    for pair in pairs {
+        let span = span_of(src, &pair);
+        match pair.as_rule() {
+            Rule::literal_expr => return parse_literal(module, src, span, pair.into_inner()),
             Rule::identifier => {
-                return Expr::Identifier(IdentifierExpr {
-                    name: pair.as_str().to_string(),
-                })
+                return Ok(Expr::new(
+                    ExprKind::Identifier(IdentifierExpr {
+                        name: pair.as_str().to_string(),
+                    }),
+                    span,
+                ))
+            }
+            Rule::node_expr => {
+                let def = parse_node_expr(module, src, pair.into_inner())?;
+                return Ok(Expr::new(ExprKind::Node(def), span));
             }
-            Rule::expr => return parse_expr(pair.into_inner()), // from "(" ~ expr ~ ")"
+            Rule::expr => return parse_expr(module, src, pair.into_inner()), // from "(" ~ expr ~ ")"
             _ => panic!("parse_expr_primary: {:?}", pair),
         }
     }
     panic!("parse_expr_primary: Reached end of input")
 }
 
-pub fn parse_expr(pairs: Pairs<Rule>) -> Expr {
+pub fn parse_expr(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<Expr, CodeExecError> {
     PRATT_PARSER
-        .map_primary(|primary| parse_expr_primary(primary.into_inner()))
-        .map_prefix(|op, rhs| match op.as_rule() {
-            Rule::pos_neg_op => {
-                if op.as_str() == "-" {
-                    Expr::Neg(NegExpr {
-                        value: Box::new(rhs),
-                    })
-                } else {
-                    rhs
+        .map_primary(|primary| parse_expr_primary(module, src, primary.into_inner()))
+        .map_prefix(|op, rhs| {
+            let rhs = rhs?;
+            let span = span_of(src, &op).to(&rhs.span);
+            let kind = match op.as_rule() {
+                Rule::pos_neg_op => {
+                    if op.as_str() == "-" {
+                        ExprKind::Neg(NegExpr {
+                            value: Box::new(rhs),
+                        })
+                    } else {
+                        return Ok(rhs);
+                    }
                 }
-            }
-            Rule::not_op => Expr::Not(NotExpr {
-                value: Box::new(rhs),
-            }),
-            Rule::empty_call_op => Expr::NodeCall(NodeCallExpr {
-                node: Box::new(rhs),
-                args: Box::new(Expr::Tuple(TupleExpr { values: vec![] })),
-            }),
-            _ => panic!("parse_expr (prefix): {:?}", op),
+                Rule::not_op => ExprKind::Not(NotExpr {
+                    value: Box::new(rhs),
+                }),
+                Rule::empty_call_op => ExprKind::NodeCall(NodeCallExpr {
+                    node: Box::new(rhs),
+                    args: Box::new(Expr::new(
+                        ExprKind::Tuple(TupleExpr { values: vec![] }),
+                        span.clone(),
+                    )),
+                }),
+                _ => panic!("parse_expr (prefix): {:?}", op),
+            };
+            Ok(Expr::new(kind, span))
         })
-        .map_infix(|mut lhs, op, rhs| match op.as_rule() {
-            Rule::add_op => Expr::Add(AddExpr {
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
-            }),
-            Rule::sub_op => Expr::Sub(SubExpr {
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
-            }),
-            Rule::mul_op => Expr::Mul(MulExpr {
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
-            }),
-            Rule::div_op => Expr::Div(DivExpr {
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
-            }),
-            Rule::mod_op => Expr::Mod(ModExpr {
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
-            }),
-            Rule::less_op => Expr::Cmp(CompareExpr {
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
-                op: CompareOp::Less,
-            }),
-            Rule::leq_op => Expr::Cmp(CompareExpr {
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
-                op: CompareOp::LessEqual,
-            }),
-            Rule::greater_op => Expr::Cmp(CompareExpr {
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
-                op: CompareOp::Greater,
-            }),
-            Rule::geq_op => Expr::Cmp(CompareExpr {
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
-                op: CompareOp::GreaterEqual,
-            }),
-            Rule::equal_op => Expr::Cmp(CompareExpr {
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
-                op: CompareOp::Equal,
-            }),
-            Rule::neq_op => Expr::Cmp(CompareExpr {
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
-                op: CompareOp::NotEqual,
-            }),
-            Rule::member_op => Expr::Member(MemberExpr {
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
-            }),
-            Rule::node_call_op => Expr::NodeCall(NodeCallExpr {
-                node: Box::new(rhs),
-                args: Box::new(lhs),
-            }),
-            Rule::tuple_op => {
-                if let Expr::Tuple(tuple) = &mut lhs {
-                    tuple.values.push(rhs);
-                    lhs
-                } else {
-                    Expr::Tuple(TupleExpr {
+        .map_infix(|lhs, op, rhs| {
+            let (mut lhs, rhs) = (lhs?, rhs?);
+            let span = lhs.span.to(&rhs.span);
+            let kind = match op.as_rule() {
+                Rule::add_op => ExprKind::Add(AddExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                }),
+                Rule::sub_op => ExprKind::Sub(SubExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                }),
+                Rule::mul_op => ExprKind::Mul(MulExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                }),
+                Rule::div_op => ExprKind::Div(DivExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                }),
+                Rule::mod_op => ExprKind::Mod(ModExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                }),
+                Rule::less_op => ExprKind::Cmp(CompareExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                    op: CompareOp::Less,
+                }),
+                Rule::leq_op => ExprKind::Cmp(CompareExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                    op: CompareOp::LessEqual,
+                }),
+                Rule::greater_op => ExprKind::Cmp(CompareExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                    op: CompareOp::Greater,
+                }),
+                Rule::geq_op => ExprKind::Cmp(CompareExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                    op: CompareOp::GreaterEqual,
+                }),
+                Rule::equal_op => ExprKind::Cmp(CompareExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                    op: CompareOp::Equal,
+                }),
+                Rule::neq_op => ExprKind::Cmp(CompareExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                    op: CompareOp::NotEqual,
+                }),
+                Rule::member_op => ExprKind::Member(MemberExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                }),
+                Rule::node_call_op => ExprKind::NodeCall(NodeCallExpr {
+                    node: Box::new(rhs),
+                    args: Box::new(lhs),
+                }),
+                Rule::tuple_op => {
+                    if let ExprKind::Tuple(tuple) = &mut lhs.kind {
+                        tuple.values.push(rhs);
+                        lhs.span = span;
+                        return Ok(lhs);
+                    }
+                    ExprKind::Tuple(TupleExpr {
                         values: vec![lhs, rhs],
                     })
                 }
-            }
-            _ => panic!("parse_expr (infix): {:?}", op),
+                _ => panic!("parse_expr (infix): {:?}", op),
+            };
+            Ok(Expr::new(kind, span))
         })
         .parse(pairs)
 }
@@ -1,23 +1,55 @@
+use std::rc::Rc;
+
+use num_bigint::BigInt;
 use pest::iterators::{Pair, Pairs};
 
 use crate::{
     data::variable::VarType,
-    expr::{Expr, TupleExpr},
-    statement::CodeExecError,
+    expr::{Expr, ExprKind, TupleExpr},
+    module::CodeModule,
+    statement::{node_def::Param, CodeExecError},
+    utils::span::{Source, Span},
 };
 
-use super::{expr::parse_expr, Rule};
+use super::{expr::parse_expr, span_of, Rule};
 
-pub fn parse_identifier_tuple(pairs: Pairs<Rule>) -> Result<Vec<String>, CodeExecError> {
-    let mut ids = Vec::new();
+/// Parses the parameters of a node into the named ones and the name of the
+/// `...rest` parameter, if any.
+pub fn parse_node_params(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<(Vec<Param>, Option<String>), CodeExecError> {
+    let mut params: Vec<Param> = Vec::new();
+    let mut rest = None;
     for pair in pairs {
         match pair.as_rule() {
-            Rule::identifier => ids.push(pair.as_str().to_string()),
+            Rule::node_param => {
+                let mut inner = pair.into_inner();
+                let name = inner.next().unwrap();
+                let span = span_of(src, &name);
+                let name = name.as_str().to_string();
+                let default = match inner.next() {
+                    Some(default) => Some(parse_expr(module, src, default.into_inner())?),
+                    None => None,
+                };
+                if default.is_none() && params.iter().any(|param| param.default.is_some()) {
+                    return Err(CodeExecError::new_str(format!(
+                        "Parameter {} needs a default, as it follows one with a default",
+                        name
+                    ))
+                    .at(&span));
+                }
+                params.push(Param { name, default });
+            }
+            Rule::rest_param => {
+                rest = Some(pair.into_inner().next().unwrap().as_str().to_string());
+            }
             Rule::tuple_op => continue,
-            _ => panic!("parse_identifier_tuple: {:?}", pair),
+            _ => panic!("parse_node_params: {:?}", pair),
         }
     }
-    Ok(ids)
+    Ok((params, rest))
 }
 
 pub fn parse_string_literal(pairs: Pairs<Rule>) -> String {
@@ -43,35 +75,70 @@ pub fn parse_string_literal(pairs: Pairs<Rule>) -> String {
     s
 }
 
-pub fn parse_tuple_literal(pairs: Pair<Rule>) -> TupleExpr {
+/// Writes `s` as a string literal that `parse_string_literal` reads back.
+pub fn string_literal(s: &str) -> String {
+    let mut literal = String::with_capacity(s.len() + 2);
+    literal.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\r' => literal.push_str("\\r"),
+            '\n' => literal.push_str("\\n"),
+            '\t' => literal.push_str("\\t"),
+            '\0' => literal.push_str("\\0"),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+pub fn parse_tuple_literal(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pair<Rule>,
+) -> Result<TupleExpr, CodeExecError> {
     let mut items = Vec::new();
     for pair in pairs.into_inner() {
         match pair.as_rule() {
-            Rule::expr => items.push(parse_expr(pair.into_inner())),
+            Rule::expr => items.push(parse_expr(module, src, pair.into_inner())?),
             _ => panic!("parse_bracket_expr: {:?}", pair),
         }
     }
-    TupleExpr { values: items }
+    Ok(TupleExpr { values: items })
 }
 
-pub fn parse_literal(pairs: Pairs<Rule>) -> Expr {
+pub fn parse_literal(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    span: Span,
+    pairs: Pairs<Rule>,
+) -> Result<Expr, CodeExecError> {
     let pair = pairs.peek().unwrap();
-    match pair.as_rule() {
-        Rule::string_literal => {
-            Expr::literal(VarType::String(parse_string_literal(pair.into_inner())))
-        }
-        Rule::int_literal => Expr::literal(VarType::Int(pair.as_str().parse::<i64>().unwrap())),
-        Rule::float_literal => Expr::literal(VarType::Float(
-            pair.as_str().replace(",", ".").parse::<f64>().unwrap(),
-        )),
-        Rule::bool_literal => Expr::literal(VarType::Bool(if pair.as_str() == "O" {
-            true
-        } else {
-            false
-        })),
-        Rule::nzero_literal => Expr::literal(VarType::Nzero),
-        Rule::bracket_expr => Expr::Bracket(parse_tuple_literal(pair)),
-        Rule::lol_literal => Expr::Lol,
+    Ok(match pair.as_rule() {
+        Rule::string_literal => Expr::literal(
+            VarType::String(parse_string_literal(pair.into_inner())),
+            span,
+        ),
+        Rule::int_literal => Expr::literal(
+            VarType::from(pair.as_str().parse::<BigInt>().unwrap()),
+            span,
+        ),
+        Rule::float_literal => Expr::literal(
+            VarType::Float(pair.as_str().replace(",", ".").parse::<f64>().unwrap()),
+            span,
+        ),
+        Rule::bool_literal => Expr::literal(
+            VarType::Bool(if pair.as_str() == "O" { true } else { false }),
+            span,
+        ),
+        Rule::nzero_literal => Expr::literal(VarType::Nzero, span),
+        Rule::bracket_expr => Expr::new(
+            ExprKind::Bracket(parse_tuple_literal(module, src, pair)?),
+            span,
+        ),
+        Rule::lol_literal => Expr::new(ExprKind::Lol, span),
         _ => panic!("parse_literal: {:?}", pair),
-    }
+    })
 }
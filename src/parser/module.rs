@@ -1,15 +1,23 @@
+use std::rc::Rc;
+
 use pest::iterators::Pairs;
 
-use crate::{data::context::Context, module::CodeModule, statement::CodeExecError};
+use crate::{
+    data::context::Context, module::CodeModule, statement::CodeExecError, utils::span::Source,
+};
 
 use super::{stmt::parse_stmt, Rule};
 
-pub fn parse_module(module: &mut CodeModule, pairs: Pairs<Rule>) -> Result<(), CodeExecError> {
+pub fn parse_module(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<(), CodeExecError> {
     Context::with(&module.ctx.clone(), || {
         for pair in pairs {
             match pair.as_rule() {
                 Rule::stmt => {
-                    let stmt = parse_stmt(module, pair.into_inner())?;
+                    let stmt = parse_stmt(module, src, pair)?;
                     module.stmts.push(stmt);
                 }
                 Rule::EOI => (),
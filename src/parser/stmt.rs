@@ -1,26 +1,31 @@
-use pest::iterators::Pairs;
+use std::rc::Rc;
+
+use pest::iterators::{Pair, Pairs};
 
 use crate::{
     expr::CompareOp,
     module::CodeModule,
-    parser::{expr::parse_lvalue, literal::parse_identifier_tuple},
+    parser::{expr::parse_lvalue_tuple, literal::parse_node_params},
     statement::{
         ass::AssStatement, expr::ExprStatement, if_stmt::IfStatement, loli::LoliStatement,
-        maybe::MaybeStatement, node_def::NodeDefStatement, ovo::OvoStatement, ret::ReturnStatement,
-        rm::RmStatement, qaq::QaqStatement, CodeExecError, Statement, Statements,
+        maybe::MaybeStatement, node_def::NodeDefStatement, ovo::OvoStatement, qaq::QaqStatement,
+        ret::ReturnStatement, rm::RmStatement, try_stmt::TryStatement, yeet::YeetStatement,
+        CodeExecError, Statement, StatementKind, Statements,
     },
+    utils::span::Source,
 };
 
-use super::{expr::parse_expr, Rule};
+use super::{expr::parse_expr, span_of, Rule};
 
-fn parse_stmt_block(
+pub fn parse_stmt_block(
     module: &mut CodeModule,
+    src: &Rc<Source>,
     pairs: Pairs<Rule>,
 ) -> Result<Statements, CodeExecError> {
     let mut stmts = Statements::new();
     for pair in pairs {
         match pair.as_rule() {
-            Rule::stmt => stmts.push(parse_stmt(module, pair.into_inner())?),
+            Rule::stmt => stmts.push(parse_stmt(module, src, pair)?),
             _ => panic!("parse_stmt_block: {:?}", pair),
         }
     }
@@ -37,14 +42,18 @@ fn parse_rm(module: &mut CodeModule, pairs: Pairs<Rule>) -> Result<RmStatement,
     panic!("parse_rm: Reached end of input")
 }
 
-fn parse_ass(pairs: Pairs<Rule>) -> Result<AssStatement, CodeExecError> {
+fn parse_ass(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<AssStatement, CodeExecError> {
     let mut lhs = None;
     let mut rhs = None;
     let mut op = CompareOp::Equal;
     for pair in pairs {
         match pair.as_rule() {
-            Rule::lvalue => lhs = Some(parse_lvalue(pair.into_inner())?),
-            Rule::expr => rhs = Some(parse_expr(pair.into_inner())),
+            Rule::lvalue_tuple => lhs = Some(parse_lvalue_tuple(module, src, pair.into_inner())?),
+            Rule::expr => rhs = Some(parse_expr(module, src, pair.into_inner())?),
             Rule::equal_op => op = CompareOp::Equal,
             Rule::neq_op => op = CompareOp::NotEqual,
             Rule::greater_op => op = CompareOp::Greater,
@@ -61,29 +70,41 @@ fn parse_ass(pairs: Pairs<Rule>) -> Result<AssStatement, CodeExecError> {
     })
 }
 
-fn parse_if(module: &mut CodeModule, pairs: Pairs<Rule>) -> Result<IfStatement, CodeExecError> {
+fn parse_if(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<IfStatement, CodeExecError> {
     let mut cond = None;
     let mut body = None;
+    let mut else_body = None;
     for pair in pairs {
         match pair.as_rule() {
-            Rule::expr => cond = Some(parse_expr(pair.into_inner())),
-            Rule::stmt => body = Some(parse_stmt(module, pair.into_inner())?),
-            _ => panic!("parse_ass: {:?}", pair),
+            Rule::expr => cond = Some(parse_expr(module, src, pair.into_inner())?),
+            Rule::stmt if body.is_none() => body = Some(parse_stmt(module, src, pair)?),
+            Rule::else_stmt_prefix => (),
+            Rule::stmt => else_body = Some(Box::new(parse_stmt(module, src, pair)?)),
+            _ => panic!("parse_if: {:?}", pair),
         }
     }
     Ok(IfStatement {
         cond: cond.unwrap(),
         body: Box::new(body.unwrap()),
+        else_body,
     })
 }
 
-fn parse_loli(module: &mut CodeModule, pairs: Pairs<Rule>) -> Result<LoliStatement, CodeExecError> {
+fn parse_loli(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<LoliStatement, CodeExecError> {
     let mut cond = None;
     let mut body = None;
     for pair in pairs {
         match pair.as_rule() {
-            Rule::expr => cond = Some(parse_expr(pair.into_inner())),
-            Rule::stmt => body = Some(parse_stmt(module, pair.into_inner())?),
+            Rule::expr => cond = Some(parse_expr(module, src, pair.into_inner())?),
+            Rule::stmt => body = Some(parse_stmt(module, src, pair)?),
             _ => panic!("parse_loli: {:?}", pair),
         }
     }
@@ -93,15 +114,19 @@ fn parse_loli(module: &mut CodeModule, pairs: Pairs<Rule>) -> Result<LoliStateme
     })
 }
 
-fn parse_ovo(module: &mut CodeModule, pairs: Pairs<Rule>) -> Result<OvoStatement, CodeExecError> {
+fn parse_ovo(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<OvoStatement, CodeExecError> {
     let mut var = None;
     let mut obj = None;
     let mut body = None;
     for pair in pairs {
         match pair.as_rule() {
             Rule::identifier => var = Some(pair.as_str().to_owned()),
-            Rule::expr => obj = Some(parse_expr(pair.into_inner())),
-            Rule::stmt => body = Some(parse_stmt(module, pair.into_inner())?),
+            Rule::expr => obj = Some(parse_expr(module, src, pair.into_inner())?),
+            Rule::stmt => body = Some(parse_stmt(module, src, pair)?),
             _ => panic!("parse_ovo: {:?}", pair),
         }
     }
@@ -112,31 +137,39 @@ fn parse_ovo(module: &mut CodeModule, pairs: Pairs<Rule>) -> Result<OvoStatement
     })
 }
 
-fn parse_qaq(module: &mut CodeModule, pairs: Pairs<Rule>) -> Result<QaqStatement, CodeExecError> {
-    let mut var = None;
+fn parse_qaq(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<QaqStatement, CodeExecError> {
+    let mut vars = None;
     let mut obj = None;
     let mut body = None;
     for pair in pairs {
         match pair.as_rule() {
-            Rule::identifier => var = Some(pair.as_str().to_owned()),
-            Rule::expr => obj = Some(parse_expr(pair.into_inner())),
-            Rule::stmt => body = Some(parse_stmt(module, pair.into_inner())?),
+            Rule::lvalue_tuple => vars = Some(parse_lvalue_tuple(module, src, pair.into_inner())?),
+            Rule::expr => obj = Some(parse_expr(module, src, pair.into_inner())?),
+            Rule::stmt => body = Some(parse_stmt(module, src, pair)?),
             _ => panic!("parse_qaq: {:?}", pair),
         }
     }
     Ok(QaqStatement {
-        var: var.unwrap(),
+        vars: vars.unwrap(),
         obj: obj.unwrap(),
         body: Box::new(body.unwrap()),
     })
 }
 
-fn parse_ret(pairs: Pairs<Rule>) -> Result<ReturnStatement, CodeExecError> {
+fn parse_ret(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<ReturnStatement, CodeExecError> {
     for pair in pairs {
         match pair.as_rule() {
             Rule::expr => {
                 return Ok(ReturnStatement {
-                    value: parse_expr(pair.into_inner()),
+                    value: parse_expr(module, src, pair.into_inner())?,
                 })
             }
             _ => panic!("parse_ret: {:?}", pair),
@@ -147,13 +180,14 @@ fn parse_ret(pairs: Pairs<Rule>) -> Result<ReturnStatement, CodeExecError> {
 
 fn parse_maybe(
     module: &mut CodeModule,
+    src: &Rc<Source>,
     pairs: Pairs<Rule>,
 ) -> Result<MaybeStatement, CodeExecError> {
     for pair in pairs {
         match pair.as_rule() {
             Rule::stmt => {
                 return Ok(MaybeStatement {
-                    body: Box::new(parse_stmt(module, pair.into_inner())?),
+                    body: Box::new(parse_stmt(module, src, pair)?),
                 })
             }
             _ => panic!("parse_maybe: {:?}", pair),
@@ -162,61 +196,106 @@ fn parse_maybe(
     panic!("parse_maybe: Reached end of input")
 }
 
+fn parse_try(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    pairs: Pairs<Rule>,
+) -> Result<TryStatement, CodeExecError> {
+    let mut body = None;
+    let mut var = None;
+    let mut handler = None;
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::stmt if body.is_none() => body = Some(parse_stmt(module, src, pair)?),
+            Rule::identifier => var = Some(pair.as_str().to_owned()),
+            Rule::stmt => handler = Some(parse_stmt(module, src, pair)?),
+            _ => panic!("parse_try: {:?}", pair),
+        }
+    }
+    Ok(TryStatement {
+        body: Box::new(body.unwrap()),
+        var: var.unwrap(),
+        handler: Box::new(handler.unwrap()),
+    })
+}
+
+fn parse_yeet(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    mut pairs: Pairs<Rule>,
+) -> Result<YeetStatement, CodeExecError> {
+    let pair = pairs.next().expect("parse_yeet: Reached end of input");
+    match pair.as_rule() {
+        Rule::expr => Ok(YeetStatement {
+            value: parse_expr(module, src, pair.into_inner())?,
+        }),
+        _ => panic!("parse_yeet: {:?}", pair),
+    }
+}
+
 fn parse_node_def(
     module: &mut CodeModule,
+    src: &Rc<Source>,
     pairs: Pairs<Rule>,
 ) -> Result<NodeDefStatement, CodeExecError> {
     let mut name = None;
-    let mut args = None;
+    let mut params = (Vec::new(), None);
     let mut body = None;
     for pair in pairs {
         match pair.as_rule() {
             Rule::identifier => name = Some(pair.as_str().to_owned()),
-            Rule::identifier_tuple => args = Some(parse_identifier_tuple(pair.into_inner())?),
-            Rule::stmt => body = Some(parse_stmt(module, pair.into_inner())?),
+            Rule::node_params => params = parse_node_params(module, src, pair.into_inner())?,
+            Rule::stmt => body = Some(parse_stmt(module, src, pair)?),
             _ => panic!("parse_node_def: {:?}", pair),
         }
     }
+    let (params, rest) = params;
     Ok(NodeDefStatement {
         name: name.unwrap(),
-        args: args.unwrap(),
+        params,
+        rest,
         body: Box::new(body.unwrap()),
+        path: module.path.to_string(),
     })
 }
 
-pub fn parse_stmt(module: &mut CodeModule, pairs: Pairs<Rule>) -> Result<Statement, CodeExecError> {
-    for pair in pairs {
-        match pair.as_rule() {
-            Rule::rm_stmt => return Ok(Statement::Rm(parse_rm(module, pair.into_inner())?)),
-            Rule::ass_stmt => return Ok(Statement::Ass(parse_ass(pair.into_inner())?)),
-            Rule::expr => {
-                return Ok(Statement::Expr(ExprStatement {
-                    value: parse_expr(pair.into_inner()),
-                }))
-            }
+pub fn parse_stmt(
+    module: &mut CodeModule,
+    src: &Rc<Source>,
+    stmt: Pair<Rule>,
+) -> Result<Statement, CodeExecError> {
+    let stmt_span = span_of(src, &stmt);
+    for pair in stmt.into_inner() {
+        let span = span_of(src, &pair);
+        let kind = match pair.as_rule() {
+            Rule::rm_stmt => StatementKind::Rm(parse_rm(module, pair.into_inner())?),
+            Rule::ass_stmt => StatementKind::Ass(parse_ass(module, src, pair.into_inner())?),
+            Rule::expr => StatementKind::Expr(ExprStatement {
+                value: parse_expr(module, src, pair.into_inner())?,
+            }),
             Rule::node_def_stmt => {
-                return Ok(Statement::NodeDef(parse_node_def(
-                    module,
-                    pair.into_inner(),
-                )?))
+                StatementKind::NodeDef(parse_node_def(module, src, pair.into_inner())?)
             }
-            Rule::if_stmt => return Ok(Statement::If(parse_if(module, pair.into_inner())?)),
-            Rule::return_stmt => return Ok(Statement::Ret(parse_ret(pair.into_inner())?)),
-            Rule::maybe_stmt => {
-                return Ok(Statement::Maybe(parse_maybe(module, pair.into_inner())?))
-            }
-            Rule::loli_stmt => return Ok(Statement::Loli(parse_loli(module, pair.into_inner())?)),
-            Rule::ovo_stmt => return Ok(Statement::Ovo(parse_ovo(module, pair.into_inner())?)),
-            Rule::qaq_stmt => return Ok(Statement::Qaq(parse_qaq(module, pair.into_inner())?)),
+            Rule::if_stmt => StatementKind::If(parse_if(module, src, pair.into_inner())?),
+            Rule::return_stmt => StatementKind::Ret(parse_ret(module, src, pair.into_inner())?),
+            Rule::break_stmt => StatementKind::Break,
+            Rule::continue_stmt => StatementKind::Continue,
+            Rule::maybe_stmt => StatementKind::Maybe(parse_maybe(module, src, pair.into_inner())?),
+            Rule::try_stmt => StatementKind::Try(parse_try(module, src, pair.into_inner())?),
+            Rule::yeet_stmt => StatementKind::Yeet(parse_yeet(module, src, pair.into_inner())?),
+            Rule::loli_stmt => StatementKind::Loli(parse_loli(module, src, pair.into_inner())?),
+            Rule::ovo_stmt => StatementKind::Ovo(parse_ovo(module, src, pair.into_inner())?),
+            Rule::qaq_stmt => StatementKind::Qaq(parse_qaq(module, src, pair.into_inner())?),
             Rule::stmt_block => {
-                return Ok(Statement::Stmts(parse_stmt_block(
-                    module,
-                    pair.into_inner(),
-                )?))
+                StatementKind::Stmts(parse_stmt_block(module, src, pair.into_inner())?)
             }
             Rule::stmt_end => continue,
             _ => panic!("parse_stmt: {:?}", pair),
-        }
+        };
+        return Ok(Statement::new(kind, span));
     }
-    Ok(Statement::Stmts(Statements::new()))
+    Ok(Statement::new(
+        StatementKind::Stmts(Statements::new()),
+        stmt_span,
+    ))
 }
@@ -0,0 +1,130 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{data::variable::VarType, interpreter::Interpreter, statement::CodeExecError};
+
+/// Interactive read-eval-print loop. All inputs run in one interpreter, so
+/// variables, nodes and loaded modules survive between lines.
+pub struct Repl {
+    interpreter: Interpreter,
+}
+
+impl Repl {
+    const PROMPT: &'static str = "lIIl> ";
+    const CONTINUE_PROMPT: &'static str = "...   ";
+    const MODULE_FILE: &'static str = "<repl>";
+
+    pub fn new() -> Self {
+        Repl {
+            interpreter: Interpreter::with_module_file(Repl::MODULE_FILE),
+        }
+    }
+
+    /// Makes random decisions repeat those of earlier runs with `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.interpreter.reseed(seed);
+    }
+
+    /// Runs one input and prints its value unless it is `N0`.
+    pub fn eval(&mut self, input: &str) -> Result<(), CodeExecError> {
+        let value = self.interpreter.eval(input)?;
+        if !matches!(value, VarType::Nzero) {
+            println!("{}", value.to_string());
+        }
+        Ok(())
+    }
+
+    pub fn run(&mut self) {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut buffer = String::new();
+        loop {
+            let prompt = if buffer.is_empty() {
+                Repl::PROMPT
+            } else {
+                Repl::CONTINUE_PROMPT
+            };
+            print!("{}", prompt);
+            io::stdout().flush().ok();
+            let line = match lines.next() {
+                Some(Ok(line)) => line,
+                _ => break,
+            };
+            buffer.push_str(&line);
+            buffer.push('\n');
+            if open_braces(&buffer) > 0 {
+                continue;
+            }
+            if let Err(e) = self.eval(&buffer) {
+                println!("{}", e.report());
+            }
+            buffer.clear();
+        }
+        println!();
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Counts unclosed `{` in `source`, ignoring braces inside string literals and
+/// comments. An unclosed block comment counts as one more.
+pub fn open_braces(source: &str) -> i64 {
+    let mut depth = 0;
+    let mut chars = source.chars().peekable();
+    let mut quote = None;
+    let mut prev = None;
+    while let Some(c) = chars.next() {
+        // Like the grammar, only look for comments between tokens, not in
+        // the middle of an identifier like `navvv`.
+        let boundary = !prev.is_some_and(is_identifier_char);
+        prev = Some(c);
+        if let Some(q) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == q || c == '\n' {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '<' | 'v' | '^' if boundary => {
+                let rest: String = chars.clone().take(2).collect();
+                if rest == "--" {
+                    chars.by_ref().find(|c| *c == '\n');
+                    prev = Some('\n');
+                } else if c == 'v' && rest == "vv" {
+                    // A block comment runs up to the next `^^^`. Without one,
+                    // `vvvx` is an identifier.
+                    let mut after = chars.clone();
+                    let mut carets = 0;
+                    let closed = after.any(|c| {
+                        carets = if c == '^' { carets + 1 } else { 0 };
+                        carets == 3
+                    });
+                    if closed {
+                        chars = after;
+                        prev = Some('^');
+                    } else if !chars
+                        .clone()
+                        .find(|c| *c != 'v')
+                        .is_some_and(is_identifier_char)
+                    {
+                        return depth + 1;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    depth
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
@@ -1,15 +1,15 @@
-use std::fmt::Debug;
+use std::fmt::{self, Debug, Display};
 
 use crate::{
-    data::context::Context,
-    data::{context::ContextRc, variable::VarType},
+    data::{context::Context, variable::VarType},
     statement::ret::ReturnStatement,
+    utils::span::Span,
 };
 
 use self::{
     ass::AssStatement, expr::ExprStatement, if_stmt::IfStatement, loli::LoliStatement,
-    maybe::MaybeStatement, node_def::NodeDefStatement, ovo::OvoStatement, rm::RmStatement,
-    qaq::QaqStatement,
+    maybe::MaybeStatement, node_def::NodeDefStatement, ovo::OvoStatement, qaq::QaqStatement,
+    rm::RmStatement, try_stmt::TryStatement, yeet::YeetStatement,
 };
 
 pub mod ass;
@@ -22,56 +22,193 @@ pub mod ovo;
 pub mod qaq;
 pub mod ret;
 pub mod rm;
+pub mod try_stmt;
+pub mod yeet;
 
-pub struct CodeExecError {
+/// One node call on the way to an error.
+#[derive(Clone)]
+pub struct TraceFrame {
+    /// Name of the called node, or the callee expression for native nodes.
+    pub name: String,
+    /// Path of the module the node was defined in, if it is a code node.
+    pub path: Option<String>,
+    pub call_site: Span,
+}
+
+impl Display for TraceFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "  at {} in {}", self.call_site, self.name)?;
+        if let Some(path) = &self.path {
+            write!(f, " ({})", path)?;
+        }
+        write!(f, "\n    {}", self.call_site.line_text().trim())
+    }
+}
+
+pub struct CodeExecError(Box<ErrorInfo>);
+
+/// Where an error happened and the node calls leading there.
+#[derive(Clone)]
+pub struct ErrorOrigin {
+    span: Option<Span>,
+    trace: Vec<TraceFrame>,
+}
+
+struct ErrorInfo {
     desc: String,
+    span: Option<Span>,
+    trace: Vec<TraceFrame>,
+    /// What lIIl code yeeted, `N0` for other errors.
+    value: VarType,
 }
 
 impl Debug for CodeExecError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&format!("CodeExecError: {}", self.desc))
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format!("CodeExecError: {}", self.0.desc))?;
+        if let Some(span) = &self.0.span {
+            write!(f, " at {}", span)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for CodeExecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.desc)?;
+        if let Some(span) = &self.0.span {
+            write!(f, "\n{}", span.excerpt())?;
+        }
+        Ok(())
     }
 }
 
 impl CodeExecError {
+    /// Identical consecutive frames beyond this count are folded into one line.
+    const MAX_REPEATED_FRAMES: usize = 3;
+
     pub fn new(_: &Context, desc: String) -> CodeExecError {
-        CodeExecError { desc }
+        CodeExecError::new_str(desc)
     }
     pub fn new_str(desc: String) -> CodeExecError {
-        CodeExecError { desc }
+        CodeExecError(Box::new(ErrorInfo {
+            desc,
+            span: None,
+            trace: Vec::new(),
+            value: VarType::Nzero,
+        }))
+    }
+
+    pub fn with_value(mut self, value: VarType) -> CodeExecError {
+        self.0.value = value;
+        self
+    }
+
+    /// Records where the error happened, unless a more precise location is
+    /// already known.
+    pub fn at(mut self, span: &Span) -> CodeExecError {
+        if self.0.span.is_none() {
+            self.0.span = Some(span.clone());
+        }
+        self
+    }
+
+    /// Where the error happened, to raise it again from the same place.
+    pub fn origin(&self) -> ErrorOrigin {
+        ErrorOrigin {
+            span: self.0.span.clone(),
+            trace: self.0.trace.clone(),
+        }
+    }
+
+    /// Places the error where `origin` happened, as if raised there.
+    pub fn with_origin(mut self, origin: &ErrorOrigin) -> CodeExecError {
+        self.0.span = origin.span.clone();
+        self.0.trace = origin.trace.clone();
+        self
+    }
+
+    /// Adds the node call the error is unwinding through.
+    pub fn called_from(mut self, frame: TraceFrame) -> CodeExecError {
+        self.0.trace.push(frame);
+        self
+    }
+
+    pub fn desc(&self) -> &str {
+        &self.0.desc
+    }
+
+    pub fn span(&self) -> Option<&Span> {
+        self.0.span.as_ref()
+    }
+
+    pub fn value(&self) -> &VarType {
+        &self.0.value
+    }
+
+    /// Node calls leading to the error, innermost first.
+    pub fn trace(&self) -> &[TraceFrame] {
+        &self.0.trace
+    }
+
+    /// Full report with the traceback, outermost call first.
+    pub fn report(&self) -> String {
+        let mut lines = Vec::new();
+        if !self.0.trace.is_empty() {
+            lines.push("Traceback (most recent call last):".to_owned());
+            let mut frames = self.0.trace.iter().rev().peekable();
+            while let Some(frame) = frames.next() {
+                let line = frame.to_string();
+                let mut repeated = 0;
+                while frames.peek().map(|f| f.to_string()) == Some(line.clone()) {
+                    frames.next();
+                    repeated += 1;
+                }
+                for _ in 0..=repeated.min(CodeExecError::MAX_REPEATED_FRAMES - 1) {
+                    lines.push(line.clone());
+                }
+                if repeated >= CodeExecError::MAX_REPEATED_FRAMES {
+                    lines.push(format!(
+                        "  [Previous frame repeated {} more times]",
+                        repeated + 1 - CodeExecError::MAX_REPEATED_FRAMES
+                    ));
+                }
+            }
+        }
+        lines.push(format!("Error: {}", self));
+        lines.join("\n")
     }
 }
 
 #[derive(Debug, Clone)]
-pub enum Statement {
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum StatementKind {
     Rm(RmStatement),
     Ass(AssStatement),
     Ret(ReturnStatement),
+    /// Leaves the innermost loop.
+    Break,
+    /// Skips to the next round of the innermost loop.
+    Continue,
     If(IfStatement),
     Loli(LoliStatement),
     Ovo(OvoStatement),
     Qaq(QaqStatement),
     Maybe(MaybeStatement),
+    Try(TryStatement),
+    Yeet(YeetStatement),
     NodeDef(NodeDefStatement),
     Expr(ExprStatement),
     Stmts(Statements),
 }
 
 impl Statement {
-    pub fn exec(&self, ctx: &ContextRc) -> Result<Option<VarType>, CodeExecError> {
-        match self {
-            Statement::Rm(stmt) => stmt.exec(ctx),
-            Statement::Ass(stmt) => stmt.exec(ctx),
-            Statement::Ret(stmt) => stmt.exec(ctx),
-            Statement::If(stmt) => stmt.exec(ctx),
-            Statement::Loli(stmt) => stmt.exec(ctx),
-            Statement::Ovo(stmt) => stmt.exec(ctx),
-            Statement::Qaq(stmt) => stmt.exec(ctx),
-            Statement::Maybe(stmt) => stmt.exec(ctx),
-            Statement::NodeDef(stmt) => stmt.exec(ctx),
-            Statement::Expr(stmt) => stmt.exec(ctx),
-            Statement::Stmts(stmt) => stmt.exec(ctx),
-        }
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Statement { kind, span }
     }
 }
 
@@ -84,14 +221,6 @@ impl Statements {
     pub fn new() -> Self {
         Statements { stmts: Vec::new() }
     }
-    pub fn exec(&self, ctx: &ContextRc) -> Result<Option<VarType>, CodeExecError> {
-        for stmt in &self.stmts {
-            if let Some(var) = stmt.exec(ctx)? {
-                return Ok(Some(var));
-            }
-        }
-        Ok(None)
-    }
     pub fn push(&mut self, stmt: Statement) {
         self.stmts.push(stmt);
     }
@@ -1,3 +1,5 @@
+use num_bigint::BigInt;
+
 use crate::{
     data::{context::ContextRc, lvalue::LValue, variable::VarType},
     expr::{CompareOp, Expr, NotExpr},
@@ -7,7 +9,7 @@ use super::CodeExecError;
 
 #[derive(Debug, Clone)]
 pub struct AssStatement {
-    pub lhs: LValue,
+    pub lhs: Vec<LValue>,
     pub rhs: Expr,
     pub op: CompareOp,
 }
@@ -56,53 +58,34 @@ fn next_down_f64(value: f64) -> f64 {
     f64::from_bits(next_bits)
 }
 
-fn next_up_i64(value: i64) -> i64 {
-    if value == i64::MAX {
-        value
-    } else {
-        value + 1
-    }
-}
-
-fn next_down_i64(value: i64) -> i64 {
-    if value == i64::MIN {
-        value
-    } else {
-        value - 1
-    }
-}
-
 impl AssStatement {
-    pub fn exec(&self, ctx: &ContextRc) -> Result<Option<VarType>, CodeExecError> {
-        let rhs = self.rhs.eval(ctx)?;
-        match &self.op {
-            CompareOp::Equal | CompareOp::GreaterEqual | CompareOp::LessEqual => {
-                self.lhs.set(ctx, rhs)?;
-                return Ok(None);
-            }
-            CompareOp::NotEqual => {
-                self.lhs.set(ctx, NotExpr::not(ctx, rhs)?)?;
-                return Ok(None);
-            }
+    /// Computes the value `mk` stores for operator `op` and right-hand side
+    /// `rhs`.
+    pub fn apply(ctx: &ContextRc, op: CompareOp, rhs: VarType) -> Result<VarType, CodeExecError> {
+        match op {
+            CompareOp::Equal | CompareOp::GreaterEqual | CompareOp::LessEqual => return Ok(rhs),
+            CompareOp::NotEqual => return NotExpr::not(ctx, rhs),
             _ => {}
         }
-        self.lhs.set(
-            ctx,
-            match (&rhs, &self.op) {
-                (VarType::Float(rhs), CompareOp::Greater) => VarType::Float(next_up_f64(*rhs)),
-                (VarType::Float(rhs), CompareOp::Less) => VarType::Float(next_down_f64(*rhs)),
-                (VarType::Int(rhs), CompareOp::Greater) => VarType::Int(next_up_i64(*rhs)),
-                (VarType::Int(rhs), CompareOp::Less) => VarType::Int(next_down_i64(*rhs)),
-                (VarType::Bool(_rhs), CompareOp::Greater) => VarType::Bool(true),
-                (VarType::Bool(_rhs), CompareOp::Less) => VarType::Bool(false),
-                _ => {
-                    return Err(CodeExecError::new(
-                        &ctx.borrow(),
-                        format!("Invalid var, op for mk: {:?} {:?}", rhs, self.op),
-                    ))
-                }
-            },
-        )?;
-        Ok(None)
+        match (&rhs, op) {
+            (VarType::Float(rhs), CompareOp::Greater) => Ok(VarType::Float(next_up_f64(*rhs))),
+            (VarType::Float(rhs), CompareOp::Less) => Ok(VarType::Float(next_down_f64(*rhs))),
+            (VarType::Int(rhs), CompareOp::Greater) => Ok(match rhs.checked_add(1) {
+                Some(value) => VarType::Int(value),
+                None => VarType::from(BigInt::from(*rhs) + 1),
+            }),
+            (VarType::Int(rhs), CompareOp::Less) => Ok(match rhs.checked_sub(1) {
+                Some(value) => VarType::Int(value),
+                None => VarType::from(BigInt::from(*rhs) - 1),
+            }),
+            (VarType::BigInt(rhs), CompareOp::Greater) => Ok(VarType::from(&**rhs + 1)),
+            (VarType::BigInt(rhs), CompareOp::Less) => Ok(VarType::from(&**rhs - 1)),
+            (VarType::Bool(_rhs), CompareOp::Greater) => Ok(VarType::Bool(true)),
+            (VarType::Bool(_rhs), CompareOp::Less) => Ok(VarType::Bool(false)),
+            _ => Err(CodeExecError::new(
+                &ctx.borrow(),
+                format!("Invalid var, op for mk: {:?} {:?}", rhs, op),
+            )),
+        }
     }
 }
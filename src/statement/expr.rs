@@ -1,9 +1,4 @@
-use crate::{
-    data::{context::ContextRc, variable::VarType},
-    expr::Expr,
-};
-
-use super::CodeExecError;
+use crate::expr::Expr;
 
 #[derive(Debug, Clone)]
 pub struct ExprStatement {
@@ -11,16 +6,7 @@ pub struct ExprStatement {
 }
 
 impl ExprStatement {
-    const THAT: &'static str = "that";
-
-    pub fn exec(&self, ctx: &ContextRc) -> Result<Option<VarType>, CodeExecError> {
-        let value = self.value.eval(ctx)?;
-        match value {
-            VarType::Nzero => Ok(None),
-            _ => {
-                ctx.borrow().set_symbol(Self::THAT, value);
-                Ok(None)
-            }
-        }
-    }
+    /// Variable holding the value of the last expression statement that did
+    /// not evaluate to `N0`.
+    pub const THAT: &'static str = "that";
 }
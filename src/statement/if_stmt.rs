@@ -1,24 +1,11 @@
-use crate::{
-    data::{context::ContextRc, variable::VarType},
-    expr::Expr,
-};
+use crate::expr::Expr;
 
-use super::{CodeExecError, Statement};
+use super::Statement;
 
 #[derive(Debug, Clone)]
 pub struct IfStatement {
     pub cond: Expr,
     pub body: Box<Statement>,
-}
-
-impl IfStatement {
-    #[inline]
-    pub fn exec(&self, ctx: &ContextRc) -> Result<Option<VarType>, CodeExecError> {
-        let rhs: bool = self.cond.eval(ctx)?.into();
-        if rhs {
-            self.body.exec(&ctx)
-        } else {
-            Ok(None)
-        }
-    }
+    /// Runs if `cond` is false. A chained else-if is an `IfStatement` here.
+    pub else_body: Option<Box<Statement>>,
 }
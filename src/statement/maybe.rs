@@ -1,8 +1,4 @@
-use rand::Rng;
-
-use crate::data::{context::ContextRc, variable::VarType};
-
-use super::{CodeExecError, Statement};
+use super::Statement;
 
 #[derive(Debug, Clone)]
 pub struct MaybeStatement {
@@ -10,14 +6,5 @@ pub struct MaybeStatement {
 }
 
 impl MaybeStatement {
-    const PROBABILITY: f64 = 0.5;
-
-    #[inline]
-    pub fn exec(&self, ctx: &ContextRc) -> Result<Option<VarType>, CodeExecError> {
-        if rand::thread_rng().gen_bool(Self::PROBABILITY) {
-            self.body.exec(ctx)
-        } else {
-            Ok(None)
-        }
-    }
+    pub const PROBABILITY: f64 = 0.5;
 }
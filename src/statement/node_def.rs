@@ -1,26 +1,26 @@
-use crate::data::{
-    context::ContextRc,
-    data::MemData,
-    node::{CodeNode, Node},
-    variable::VarType,
-};
+use crate::expr::Expr;
 
-use super::{CodeExecError, Statement};
+use super::Statement;
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    /// Evaluated in the scope of the call when the argument is left out.
+    pub default: Option<Expr>,
+}
 
 #[derive(Debug, Clone)]
 pub struct NodeDefStatement {
     pub name: String,
-    pub args: Vec<String>,
+    pub params: Vec<Param>,
+    /// Name of the `...rest` parameter.
+    pub rest: Option<String>,
     pub body: Box<Statement>,
+    /// Path of the module the node is defined in.
+    pub path: String,
 }
 
 impl NodeDefStatement {
-    pub fn exec(&self, ctx: &ContextRc) -> Result<Option<VarType>, CodeExecError> {
-        let mut code_node = CodeNode::new(ctx, self.body.clone());
-        code_node.args = self.args.clone();
-        let node = Node::Code(code_node);
-        let var = MemData::new_rc(MemData::Node(node));
-        ctx.borrow().set_symbol(&self.name, VarType::Ref(var));
-        Ok(None)
-    }
+    /// Name of nodes written as `(args << body)` in tracebacks.
+    pub const ANONYMOUS: &'static str = "<anonymous>";
 }
@@ -1,5 +1,9 @@
 use crate::{
-    data::{context::ContextRc, data::MemData, variable::VarType},
+    data::{
+        context::ContextRc,
+        data::{MemData, MemDataRc},
+        variable::VarType,
+    },
     expr::Expr,
 };
 
@@ -13,32 +17,21 @@ pub struct OvoStatement {
 }
 
 impl OvoStatement {
-    #[inline]
-    pub fn exec(&self, ctx: &ContextRc) -> Result<Option<VarType>, CodeExecError> {
-        let val = self.obj.eval(ctx)?;
-        let keys = match val {
+    /// The object `val` and its keys to iterate over, taken before the loop
+    /// starts.
+    pub fn keys(ctx: &ContextRc, val: VarType) -> Result<(MemDataRc, Vec<String>), CodeExecError> {
+        match val {
             VarType::Ref(data) => match &*data.borrow() {
-                MemData::Object(obj) => obj.keys(),
-                _ => {
-                    return Err(CodeExecError::new(
-                        &ctx.borrow(),
-                        "ovo: expected an object (lol) to iterate over".to_string(),
-                    ))
-                }
-            },
-            _ => {
-                return Err(CodeExecError::new(
+                MemData::Object(obj) => Ok((data.clone(), obj.keys())),
+                _ => Err(CodeExecError::new(
                     &ctx.borrow(),
-                    "ovo: expected a reference to an object (lol)".to_string(),
-                ))
-            }
-        };
-        for key in keys {
-            ctx.borrow().set_symbol(&self.var, VarType::String(key));
-            if let Some(ret) = self.body.exec(ctx)? {
-                return Ok(Some(ret));
-            }
+                    "ovo: expected an object (lol) to iterate over".to_string(),
+                )),
+            },
+            _ => Err(CodeExecError::new(
+                &ctx.borrow(),
+                "ovo: expected a reference to an object (lol)".to_string(),
+            )),
         }
-        Ok(None)
     }
 }
@@ -1,5 +1,10 @@
 use crate::{
-    data::{context::ContextRc, data::MemData, variable::VarType},
+    data::{
+        context::ContextRc,
+        data::{MemData, MemDataRc},
+        lvalue::LValue,
+        variable::VarType,
+    },
     expr::Expr,
 };
 
@@ -7,15 +12,16 @@ use super::{CodeExecError, Statement};
 
 #[derive(Debug, Clone)]
 pub struct QaqStatement {
-    pub var: String,
+    pub vars: Vec<LValue>,
     pub obj: Expr,
     pub body: Box<Statement>,
 }
 
 impl QaqStatement {
-    pub fn exec(&self, ctx: &ContextRc) -> Result<Option<VarType>, CodeExecError> {
-        let val = self.obj.eval(ctx)?;
-        let (len, data_clone) = match val {
+    /// Length and data of the list `val` to iterate over. The length is taken
+    /// before the loop starts; items are read as the loop reaches them.
+    pub fn list(ctx: &ContextRc, val: VarType) -> Result<(i64, MemDataRc), CodeExecError> {
+        Ok(match val {
             VarType::Ref(data) => {
                 let borrowed = data.borrow();
                 match &*borrowed {
@@ -41,22 +47,15 @@ impl QaqStatement {
                 &ctx.borrow(),
                 "qaq: expected a reference to an object (lol)".to_string(),
             )),
-        };
+        })
+    }
 
-        for i in 0..len {
-            let item_val = {
-                let borrowed = data_clone.borrow();
-                if let MemData::Object(obj) = &*borrowed {
-                    obj.get(&i.to_string())
-                } else {
-                    None
-                }
-            };
-            ctx.borrow().set_symbol(&self.var, item_val.unwrap_or(VarType::Nzero));
-            if let Some(ret) = self.body.exec(ctx)? {
-                return Ok(Some(ret));
-            }
+    pub fn item(data: &MemDataRc, i: i64) -> VarType {
+        let borrowed = data.borrow();
+        if let MemData::Object(obj) = &*borrowed {
+            obj.get(&i.to_string()).unwrap_or(VarType::Nzero)
+        } else {
+            VarType::Nzero
         }
-        Ok(None)
     }
 }
@@ -1,5 +1,5 @@
 use crate::{
-    data::{context::ContextRc, variable::VarType},
+    data::context::ContextRc,
     module::{CodeModule, Module},
     parser::parse_file,
     utils::path::Path,
@@ -20,18 +20,16 @@ impl RmStatement {
             parent_path: module.path.parent(),
         }
     }
-    pub fn exec(&self, ctx: &ContextRc) -> Result<Option<VarType>, CodeExecError> {
+    pub fn exec(&self, ctx: &ContextRc) -> Result<(), CodeExecError> {
         let global = ctx.borrow_mut().get_global();
-        let symbol_name = std::path::Path::new(&self.path)
-            .file_stem()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
+        let symbol_name = match self.path.rsplit_once('/') {
+            Some((_, name)) => name.to_owned(),
+            None => self.path.clone(),
+        };
 
         // Module already exists.
         if ctx.borrow().has_symbol(&symbol_name) {
-            return Ok(None);
+            return Ok(());
         }
 
         // Built-in module.
@@ -40,7 +38,7 @@ impl RmStatement {
             let mut module = factory(&ctx.borrow().get_root());
             let module_ret = module.exec()?;
             ctx.borrow_mut().set_symbol(&self.path, module_ret);
-            return Ok(None);
+            return Ok(());
         }
 
         // Code module.
@@ -54,21 +52,30 @@ impl RmStatement {
             resolved_path = std::path::PathBuf::from(&code_path);
         }
         
-        // 3. Try in built-in search path
+        // 3. Built-in module written in lIIl.
         if !resolved_path.is_file() {
-            resolved_path = std::path::PathBuf::from("src/builtin").join(&code_path);
+            let factory = global.borrow().builtin_modules.get_fallback(&self.path);
+            let Some(factory) = factory else {
+                return Err(CodeExecError::new(
+                    &ctx.borrow(),
+                    format!("Module {} not found", self.path),
+                ));
+            };
+            let mut module = factory(&ctx.borrow().get_root())?;
+            let module_ret = module.exec()?;
+            ctx.borrow_mut().set_symbol(&symbol_name, module_ret);
+            return Ok(());
         }
-        
-        if !resolved_path.is_file() {
+
+        let Some(resolved_path) = resolved_path.to_str() else {
             return Err(CodeExecError::new(
                 &ctx.borrow(),
-                format!("Module {} not found", self.path),
+                format!("Module {} has a path that is not valid UTF-8", self.path),
             ));
-        }
-        
-        let mut module = parse_file(resolved_path.to_str().unwrap(), Some(ctx.borrow().get_root()))?;
+        };
+        let mut module = parse_file(resolved_path, Some(ctx.borrow().get_root()))?;
         let module_ret = module.exec()?;
         ctx.borrow_mut().set_symbol(&symbol_name, module_ret);
-        Ok(None)
+        Ok(())
     }
 }
@@ -0,0 +1,37 @@
+use crate::{
+    data::{
+        context::ContextRc,
+        data::{MemData, Object},
+        variable::VarType,
+    },
+    statement::CodeExecError,
+};
+
+use super::Statement;
+
+#[derive(Debug, Clone)]
+pub struct TryStatement {
+    pub body: Box<Statement>,
+    /// Variable the `oops` handler gets the error in.
+    pub var: String,
+    pub handler: Box<Statement>,
+}
+
+impl TryStatement {
+    /// Describes a caught error as a `lol` with its `msg`, the `path`, `line`
+    /// and `col` it happened at if known, and the `value` it was yeeted with.
+    pub fn error_object(ctx: &ContextRc, error: &CodeExecError) -> VarType {
+        let mut obj = Object::with_origin(error.origin());
+        obj.set("msg", VarType::String(error.desc().to_owned()));
+        if let Some(span) = error.span() {
+            let (line, col) = span.line_col();
+            obj.set("path", VarType::String(span.path().to_owned()));
+            obj.set("line", VarType::Int(line as i64));
+            obj.set("col", VarType::Int(col as i64));
+        }
+        obj.set("value", error.value().clone());
+        let rc = MemData::new_rc(MemData::Object(obj));
+        ctx.borrow().get_global().borrow_mut().register_object(&rc);
+        VarType::Ref(rc)
+    }
+}
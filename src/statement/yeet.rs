@@ -0,0 +1,39 @@
+use crate::{
+    data::{data::MemData, variable::VarType},
+    expr::Expr,
+    statement::CodeExecError,
+};
+
+#[derive(Debug, Clone)]
+pub struct YeetStatement {
+    pub value: Expr,
+}
+
+impl YeetStatement {
+    /// The error raised by yeeting `value`. A `String` is the message itself,
+    /// a `lol` with a `msg`, such as a caught error, supplies its own. A
+    /// caught error is raised again from where it first happened.
+    pub fn error(value: VarType) -> CodeExecError {
+        let mut origin = None;
+        let desc = match &value {
+            VarType::String(msg) => msg.clone(),
+            VarType::Ref(data) => match &*data.borrow() {
+                MemData::Object(obj) => {
+                    origin = obj.origin();
+                    match obj.get("msg") {
+                        Some(VarType::String(msg)) => msg,
+                        _ => "Yeeted a lol without msg".to_owned(),
+                    }
+                }
+                MemData::Mess(_) => "Yeeted a module".to_owned(),
+                MemData::Node(_) => "Yeeted a node".to_owned(),
+            },
+            value => format!("Yeeted {}", value.to_string()),
+        };
+        let error = CodeExecError::new_str(desc).with_value(value);
+        match origin {
+            Some(origin) => error.with_origin(&origin),
+            None => error,
+        }
+    }
+}
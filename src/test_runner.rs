@@ -0,0 +1,194 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use rand::Rng;
+
+use crate::{
+    data::{data::MemData, variable::VarType},
+    parser::parse_file,
+    statement::{CodeExecError, StatementKind},
+};
+
+/// A test: a whole `.lIIl` file, or one `test_*` node defined at its top
+/// level.
+pub struct TestCase {
+    pub path: PathBuf,
+    pub node: Option<String>,
+}
+
+impl TestCase {
+    pub fn name(&self) -> String {
+        match &self.node {
+            Some(node) => format!("{}::{}", self.path.display(), node),
+            None => self.path.display().to_string(),
+        }
+    }
+}
+
+pub struct TestResult {
+    pub name: String,
+    pub error: Option<CodeExecError>,
+    /// Seed of the run, for reproducing a failure.
+    pub seed: u64,
+    pub duration: Duration,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Finds and runs lIIl tests. Every test runs in a fresh root context, so
+/// tests cannot see each other's variables or loaded modules.
+pub struct TestRunner {
+    seed: Option<u64>,
+}
+
+impl TestRunner {
+    const NODE_PREFIX: &'static str = "test_";
+    const LIIL_EXT: &'static str = "lIIl";
+
+    pub fn new(seed: Option<u64>) -> Self {
+        TestRunner { seed }
+    }
+
+    /// Collects the tests under `dir`, in path order. A file defining
+    /// `test_*` nodes yields one test per node, any other file is a test by
+    /// itself.
+    pub fn discover(dir: &Path) -> io::Result<Vec<TestCase>> {
+        let mut files = Vec::new();
+        TestRunner::find_files(dir, &mut files)?;
+        files.sort();
+        let mut cases = Vec::new();
+        for path in files {
+            let nodes = TestRunner::test_nodes(&path);
+            if nodes.is_empty() {
+                cases.push(TestCase { path, node: None });
+            } else {
+                cases.extend(nodes.into_iter().map(|node| TestCase {
+                    path: path.clone(),
+                    node: Some(node),
+                }));
+            }
+        }
+        Ok(cases)
+    }
+
+    fn find_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        if dir.is_file() {
+            files.push(dir.to_owned());
+            return Ok(());
+        }
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                TestRunner::find_files(&path, files)?;
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext == TestRunner::LIIL_EXT)
+            {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Names of the `test_*` nodes defined at the top level of `path`. A file
+    /// that does not parse has none, and fails as a whole when run.
+    fn test_nodes(path: &Path) -> Vec<String> {
+        let Ok(module) = parse_file(&path.to_string_lossy(), None) else {
+            return Vec::new();
+        };
+        module
+            .stmts
+            .stmts
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::NodeDef(def) if def.name.starts_with(TestRunner::NODE_PREFIX) => {
+                    Some(def.name.clone())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn run_case(&self, case: &TestCase) -> TestResult {
+        let seed = self.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let start = Instant::now();
+        let error = TestRunner::exec_case(case, seed).err();
+        TestResult {
+            name: case.name(),
+            error,
+            seed,
+            duration: start.elapsed(),
+        }
+    }
+
+    fn exec_case(case: &TestCase, seed: u64) -> Result<(), CodeExecError> {
+        let mut module = parse_file(&case.path.to_string_lossy(), None)?;
+        let global = module.ctx.borrow().get_global();
+        global.borrow_mut().reseed(seed);
+        module.exec()?;
+        let Some(name) = &case.node else {
+            return Ok(());
+        };
+        let node = match module.ctx.borrow().get_symbol(name) {
+            Some(VarType::Ref(data)) => match &*data.borrow() {
+                MemData::Node(node) => Some(node.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        match node {
+            Some(mut node) => node.exec(&Vec::new()).map(|_| ()),
+            None => Err(CodeExecError::new_str(format!("{} is not a node", name))),
+        }
+    }
+
+    /// Runs every test under `dir`, printing each result as it finishes and a
+    /// summary at the end. Returns whether all tests passed.
+    pub fn run(&self, dir: &str) -> bool {
+        let cases = match TestRunner::discover(Path::new(dir)) {
+            Ok(cases) => cases,
+            Err(e) => {
+                println!("Error: cannot read {}: {}", dir, e);
+                return false;
+            }
+        };
+        let start = Instant::now();
+        let mut failed = 0;
+        for case in &cases {
+            let result = self.run_case(case);
+            let duration = TestRunner::format_duration(result.duration);
+            if result.passed() {
+                println!("ok   {} ({})", result.name, duration);
+                continue;
+            }
+            failed += 1;
+            println!("FAIL {} ({})", result.name, duration);
+            if let Some(e) = &result.error {
+                println!("{}", e.report());
+            }
+            if self.seed.is_none() {
+                let seed = result.seed;
+                println!("Random seed: {seed}. Rerun with --seed {seed} to reproduce.");
+            }
+        }
+        println!();
+        println!(
+            "{} passed, {} failed in {}",
+            cases.len() - failed,
+            failed,
+            TestRunner::format_duration(start.elapsed())
+        );
+        failed == 0
+    }
+
+    fn format_duration(duration: Duration) -> String {
+        format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+    }
+}
@@ -0,0 +1,110 @@
+use std::{fmt, rc::Rc};
+
+/// A piece of lIIl source code together with the path it was loaded from.
+pub struct Source {
+    pub path: String,
+    pub text: String,
+}
+
+impl Source {
+    pub fn new_rc(path: &str, text: &str) -> Rc<Source> {
+        Rc::new(Source {
+            path: path.to_owned(),
+            text: text.to_owned(),
+        })
+    }
+}
+
+/// A byte range inside a `Source`.
+#[derive(Clone)]
+pub struct Span {
+    source: Rc<Source>,
+    start: usize,
+    end: usize,
+}
+
+impl Span {
+    pub fn new(source: &Rc<Source>, start: usize, end: usize) -> Self {
+        Span {
+            source: source.clone(),
+            start,
+            end,
+        }
+    }
+
+    /// Returns a span from the start of `self` to the end of `other`.
+    pub fn to(&self, other: &Span) -> Span {
+        Span {
+            source: self.source.clone(),
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.source.path
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source.text[self.start..self.end]
+    }
+
+    /// 1-based line and column (in characters) of the start of the span.
+    pub fn line_col(&self) -> (usize, usize) {
+        let before = &self.source.text[..self.start];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let col = before[line_start..].chars().count() + 1;
+        (line, col)
+    }
+
+    /// The full source line the span starts on.
+    pub fn line_text(&self) -> &str {
+        let text = &self.source.text;
+        let line_start = text[..self.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = text[self.start..]
+            .find('\n')
+            .map(|i| self.start + i)
+            .unwrap_or(text.len());
+        text[line_start..line_end].trim_end_matches('\r')
+    }
+
+    /// Renders the location followed by the source line with the span
+    /// underlined by carets.
+    pub fn excerpt(&self) -> String {
+        let (line, col) = self.line_col();
+        let width = self
+            .as_str()
+            .lines()
+            .next()
+            .map(|s| s.chars().count())
+            .unwrap_or(0)
+            .max(1);
+        let gutter = " ".repeat(line.to_string().len());
+        // Tabs are kept so that the carets line up however they are shown.
+        let pad: String = self
+            .line_text()
+            .chars()
+            .take(col - 1)
+            .map(|c| if c.is_whitespace() { c } else { ' ' })
+            .collect();
+        format!(
+            "{gutter}--> {self}\n{gutter} |\n{line} | {}\n{gutter} | {pad}{}",
+            self.line_text(),
+            "^".repeat(width),
+        )
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (line, col) = self.line_col();
+        write!(f, "{}:{}:{}", self.path(), line, col)
+    }
+}
+
+impl fmt::Debug for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
@@ -0,0 +1,396 @@
+use rand::Rng;
+
+use crate::{
+    data::{
+        context::ContextRc,
+        data::{MemData, MemDataRc, Object, Tuple},
+        node::{CodeNode, Node},
+        variable::VarType,
+    },
+    expr::{
+        AddExpr, CompareExpr, CompareOp, DivExpr, MemberExpr, ModExpr, MulExpr, NegExpr,
+        NodeCallExpr, NotExpr, SubExpr,
+    },
+    statement::{
+        ass::AssStatement, maybe::MaybeStatement, ovo::OvoStatement, qaq::QaqStatement,
+        try_stmt::TryStatement, yeet::YeetStatement, CodeExecError,
+    },
+};
+
+use self::chunk::{Chunk, Op};
+
+pub mod chunk;
+pub mod compiler;
+
+/// State of an `ovo` or `qaq` loop.
+enum Iter {
+    /// Keys of an object, skipping those removed since the loop started.
+    Keys {
+        data: MemDataRc,
+        keys: std::vec::IntoIter<String>,
+    },
+    List {
+        data: MemDataRc,
+        len: i64,
+        next: i64,
+    },
+}
+
+impl Iterator for Iter {
+    type Item = VarType;
+
+    fn next(&mut self) -> Option<VarType> {
+        match self {
+            Iter::Keys { data, keys } => {
+                keys.find(|key| data.borrow().has(key)).map(VarType::String)
+            }
+            Iter::List { data, len, next } => {
+                if *next >= *len {
+                    return None;
+                }
+                *next += 1;
+                Some(QaqStatement::item(data, *next - 1))
+            }
+        }
+    }
+}
+
+/// An `oops` handler waiting for errors in its `yolo` block.
+struct Handler {
+    pc: usize,
+    /// Heights of the value and iteration stacks when the block started.
+    stack: usize,
+    iters: usize,
+}
+
+struct Frame<'a> {
+    chunk: &'a Chunk,
+    ctx: &'a ContextRc,
+    stack: Vec<VarType>,
+    /// Variables of `ctx`, kept at hand so that reading or overwriting a
+    /// variable of the frame's own scope skips the context.
+    mess: MemDataRc,
+    iters: Vec<Iter>,
+    handlers: Vec<Handler>,
+    pc: usize,
+}
+
+/// Runs `chunk` in `ctx` and returns the value of its `=>` statement, or
+/// `N0` if it runs to the end.
+pub fn run(chunk: &Chunk, ctx: &ContextRc) -> Result<VarType, CodeExecError> {
+    let mut frame = Frame {
+        chunk,
+        ctx,
+        stack: Vec::new(),
+        mess: ctx.borrow().get_mess(),
+        iters: Vec::new(),
+        handlers: Vec::new(),
+        pc: 0,
+    };
+    frame.run().map_err(|e| e.at(&chunk.spans[frame.pc - 1]))
+}
+
+/// Integer operands are by far the most common, so they skip the type
+/// promotion of the general case. Overflow and division by zero are left to
+/// the general case.
+#[inline]
+fn int_binary(op: Op, l: i64, r: i64) -> Option<VarType> {
+    Some(match op {
+        Op::Add => VarType::Int(l.checked_add(r)?),
+        Op::Sub => VarType::Int(l.checked_sub(r)?),
+        Op::Mul => VarType::Int(l.checked_mul(r)?),
+        Op::Div => VarType::Int(l.checked_div(r)?),
+        Op::Mod => VarType::Int(l.checked_rem(r)?),
+        Op::Cmp(op) => VarType::Bool(int_compare(op, l, r)),
+        _ => unreachable!("not a binary operator: {:?}", op),
+    })
+}
+
+/// `mk i > i.` and `mk i < i.` count loops, so stepping an integer skips
+/// `AssStatement::apply` unless it overflows.
+#[inline]
+fn int_assign(op: CompareOp, i: i64) -> Option<VarType> {
+    match op {
+        CompareOp::Greater => i.checked_add(1).map(VarType::Int),
+        CompareOp::Less => i.checked_sub(1).map(VarType::Int),
+        _ => None,
+    }
+}
+
+#[inline]
+fn int_compare(op: CompareOp, l: i64, r: i64) -> bool {
+    match op {
+        CompareOp::Less => l < r,
+        CompareOp::Greater => l > r,
+        CompareOp::Equal => l == r,
+        CompareOp::NotEqual => l != r,
+        CompareOp::LessEqual => l <= r,
+        CompareOp::GreaterEqual => l >= r,
+    }
+}
+
+#[inline(never)]
+fn binary(ctx: &ContextRc, op: Op, lhs: VarType, rhs: VarType) -> Result<VarType, CodeExecError> {
+    match op {
+        Op::Add => AddExpr::apply(ctx, lhs, rhs),
+        Op::Sub => SubExpr::apply(ctx, lhs, rhs),
+        Op::Mul => MulExpr::apply(ctx, lhs, rhs),
+        Op::Div => DivExpr::apply(ctx, lhs, rhs),
+        Op::Mod => ModExpr::apply(ctx, lhs, rhs),
+        Op::Cmp(op) => CompareExpr::apply(ctx, op, lhs, rhs),
+        _ => unreachable!("not a binary operator: {:?}", op),
+    }
+}
+
+impl<'a> Frame<'a> {
+    #[inline]
+    fn pop(&mut self) -> VarType {
+        self.stack.pop().expect("VM stack underflow")
+    }
+
+    #[inline]
+    fn pop2(&mut self) -> (VarType, VarType) {
+        let rhs = self.pop();
+        (self.pop(), rhs)
+    }
+
+    /// Pushes the variable in `slot` if it is set in this frame's own scope.
+    #[inline]
+    fn push_local(&mut self, slot: u32) -> bool {
+        if let MemData::Mess(mess) = &*self.mess.borrow() {
+            if let Some(var) = mess.slot(slot as usize) {
+                self.stack.push(var.clone());
+                return true;
+            }
+        }
+        false
+    }
+
+    fn load(&self, slot: u32) -> Result<VarType, CodeExecError> {
+        let slot = slot as usize;
+        self.ctx
+            .borrow()
+            .get_slot(slot, self.chunk.layout.name(slot))
+    }
+
+    fn store(&self, slot: u32, value: VarType) {
+        let slot = slot as usize;
+        if let MemData::Mess(m) = &mut *self.mess.borrow_mut() {
+            if m.slot(slot).is_some() {
+                m.set_slot(slot, value);
+                return;
+            }
+        }
+        self.ctx
+            .borrow()
+            .set_slot(slot, self.chunk.layout.name(slot), value);
+    }
+
+    fn run(&mut self) -> Result<VarType, CodeExecError> {
+        loop {
+            match self.exec() {
+                Err(e) if !self.handlers.is_empty() => self.catch(e),
+                result => return result,
+            }
+        }
+    }
+
+    /// Resumes at the innermost `oops` handler with `error` on the stack.
+    fn catch(&mut self, error: CodeExecError) {
+        let error = error.at(&self.chunk.spans[self.pc - 1]);
+        let handler = self.handlers.pop().expect("catch: no handler");
+        self.stack.truncate(handler.stack);
+        self.iters.truncate(handler.iters);
+        self.stack
+            .push(TryStatement::error_object(self.ctx, &error));
+        self.pc = handler.pc;
+    }
+
+    fn exec(&mut self) -> Result<VarType, CodeExecError> {
+        let chunk = self.chunk;
+        let ctx = self.ctx;
+        while let Some(op) = chunk.code.get(self.pc) {
+            self.pc += 1;
+            let op = *op;
+            match op {
+                Op::Const(idx) => self.stack.push(chunk.consts[idx as usize].clone()),
+                Op::Lol => {
+                    let rc = MemData::new_rc(MemData::Object(Object::new()));
+                    ctx.borrow().get_global().borrow_mut().register_object(&rc);
+                    self.stack.push(VarType::Ref(rc));
+                }
+                Op::Load(slot) => {
+                    if !self.push_local(slot) {
+                        let value = self.load(slot)?;
+                        self.stack.push(value);
+                    }
+                }
+                Op::Store(slot) => {
+                    let value = self.pop();
+                    self.store(slot, value);
+                }
+                Op::That(slot) => {
+                    let value = self.pop();
+                    if !matches!(value, VarType::Nzero) {
+                        self.store(slot, value);
+                    }
+                }
+                Op::Dup => {
+                    let value = self.stack.last().expect("VM stack underflow").clone();
+                    self.stack.push(value);
+                }
+                Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Mod | Op::Cmp(_) => {
+                    let (lhs, rhs) = self.pop2();
+                    let value = match (lhs, rhs) {
+                        (VarType::Int(l), VarType::Int(r)) => match int_binary(op, l, r) {
+                            Some(value) => value,
+                            None => binary(ctx, op, VarType::Int(l), VarType::Int(r))?,
+                        },
+                        (lhs, rhs) => binary(ctx, op, lhs, rhs)?,
+                    };
+                    self.stack.push(value);
+                }
+                Op::Not => {
+                    let value = self.pop();
+                    self.stack.push(NotExpr::not(ctx, value)?);
+                }
+                Op::Neg => {
+                    let value = self.pop();
+                    self.stack.push(NegExpr::apply(ctx, value)?);
+                }
+                Op::Tuple(len) => {
+                    let items = self.stack.split_off(self.stack.len() - len as usize);
+                    self.stack.push(VarType::Tuple(Tuple { items }));
+                }
+                Op::Unpack(len) => match self.pop() {
+                    VarType::Tuple(tuple) if tuple.items.len() == len as usize => {
+                        self.stack.extend(tuple.items.into_iter().rev());
+                    }
+                    value => {
+                        return Err(CodeExecError::new(
+                            &ctx.borrow(),
+                            format!(
+                                "Expected a tuple of {} values, got {}",
+                                len,
+                                value.to_string()
+                            ),
+                        ))
+                    }
+                },
+                Op::GetMember => {
+                    let (key, parent) = self.pop2();
+                    self.stack.push(MemberExpr::get(ctx, key, parent)?);
+                }
+                Op::SetMember => {
+                    let (key, parent) = self.pop2();
+                    let value = self.pop();
+                    MemberExpr::set(ctx, key, parent, value)?;
+                }
+                Op::Call(idx) => {
+                    let site = &chunk.calls[idx as usize];
+                    let args = if site.spread {
+                        match self.pop() {
+                            VarType::Tuple(tuple) => tuple.items,
+                            value => vec![value],
+                        }
+                    } else {
+                        self.stack.split_off(self.stack.len() - site.argc)
+                    };
+                    let node = self.pop();
+                    let call_site = &chunk.spans[self.pc - 1];
+                    let value = NodeCallExpr::call(ctx, node, &args, call_site, &site.callee)?;
+                    self.stack.push(value);
+                }
+                Op::Assign(op) => {
+                    let value = match self.pop() {
+                        VarType::Int(i) => match int_assign(op, i) {
+                            Some(value) => value,
+                            None => AssStatement::apply(ctx, op, VarType::Int(i))?,
+                        },
+                        value => AssStatement::apply(ctx, op, value)?,
+                    };
+                    self.stack.push(value);
+                }
+                Op::Jump(target) => self.pc = target as usize,
+                Op::JumpIfSet(slot, target) => {
+                    if let MemData::Mess(mess) = &*self.mess.borrow() {
+                        if mess.slot(slot as usize).is_some() {
+                            self.pc = target as usize;
+                        }
+                    }
+                }
+                Op::JumpUnless(op, target) => {
+                    let run = match self.pop2() {
+                        (VarType::Int(l), VarType::Int(r)) => int_compare(op, l, r),
+                        (lhs, rhs) => bool::from(CompareExpr::apply(ctx, op, lhs, rhs)?),
+                    };
+                    if !run {
+                        self.pc = target as usize;
+                    }
+                }
+                Op::JumpIfFalse(target) => {
+                    if !bool::from(self.pop()) {
+                        self.pc = target as usize;
+                    }
+                }
+                Op::Maybe(target) => {
+                    let run = ctx
+                        .borrow()
+                        .get_global()
+                        .borrow_mut()
+                        .rng
+                        .gen_bool(MaybeStatement::PROBABILITY);
+                    if !run {
+                        self.pc = target as usize;
+                    }
+                }
+                Op::IterKeys => {
+                    let value = self.pop();
+                    let (data, keys) = OvoStatement::keys(ctx, value)?;
+                    self.iters.push(Iter::Keys {
+                        data,
+                        keys: keys.into_iter(),
+                    });
+                }
+                Op::IterList => {
+                    let value = self.pop();
+                    let (len, data) = QaqStatement::list(ctx, value)?;
+                    self.iters.push(Iter::List { data, len, next: 0 });
+                }
+                Op::IterNext(target) => match self.iters.last_mut().and_then(|iter| iter.next()) {
+                    Some(value) => self.stack.push(value),
+                    None => {
+                        self.iters.pop();
+                        self.pc = target as usize;
+                    }
+                },
+                Op::IterEnd => {
+                    self.iters.pop();
+                }
+                Op::Try(target) => self.handlers.push(Handler {
+                    pc: target as usize,
+                    stack: self.stack.len(),
+                    iters: self.iters.len(),
+                }),
+                Op::EndTry => {
+                    self.handlers.pop();
+                }
+                Op::Yeet => {
+                    let value = self.pop();
+                    return Err(YeetStatement::error(value));
+                }
+                Op::MakeNode(idx) => {
+                    let node = Node::Code(CodeNode::new(ctx, &chunk.nodes[idx as usize]));
+                    let rc = MemData::new_rc(MemData::Node(node));
+                    let global = ctx.borrow().get_global();
+                    global.borrow_mut().register_context(ctx);
+                    global.borrow_mut().register_object(&rc);
+                    self.stack.push(VarType::Ref(rc));
+                }
+                Op::Rm(idx) => chunk.modules[idx as usize].exec(ctx)?,
+                Op::Return => return Ok(self.pop()),
+            }
+        }
+        Ok(VarType::Nzero)
+    }
+}
@@ -0,0 +1,122 @@
+use std::rc::Rc;
+
+use crate::{
+    data::{data::Layout, variable::VarType},
+    expr::CompareOp,
+    statement::rm::RmStatement,
+    utils::span::Span,
+};
+
+/// One VM instruction. Operands are indices into the tables of the `Chunk`
+/// the instruction belongs to, slots of its `layout`, or jump targets.
+#[derive(Clone, Copy, Debug)]
+pub enum Op {
+    Const(u32),
+    Lol,
+    Load(u32),
+    Store(u32),
+    /// Pops a value and stores it in the `that` slot unless it is `N0`.
+    That(u32),
+    /// Pushes a copy of the top value.
+    Dup,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Cmp(CompareOp),
+    Not,
+    Neg,
+    Tuple(u32),
+    /// Pops a tuple of exactly that many items and pushes them, the first on
+    /// top.
+    Unpack(u32),
+    /// Pops parent and key, pushes `key@parent`.
+    GetMember,
+    /// Pops parent, key and value, sets `key@parent`.
+    SetMember,
+    Call(u32),
+    /// Applies the operator of a `mk` statement to the top of the stack.
+    Assign(CompareOp),
+    Jump(u32),
+    JumpIfFalse(u32),
+    /// Pops two values and jumps unless they compare true; a `Cmp` followed
+    /// by `JumpIfFalse`.
+    JumpUnless(CompareOp, u32),
+    /// Jumps unless the coin lands on running the `maybe` body.
+    Maybe(u32),
+    /// Pops an object and starts iterating over its keys.
+    IterKeys,
+    /// Pops a list and starts iterating over its items.
+    IterList,
+    /// Pushes the next item of the innermost iteration, or ends the iteration
+    /// and jumps.
+    IterNext(u32),
+    /// Ends the innermost iteration early.
+    IterEnd,
+    /// Starts a `yolo` block whose `oops` handler is at the target.
+    Try(u32),
+    /// Ends the innermost `yolo` block.
+    EndTry,
+    /// Jumps if the slot is set in the frame's own scope.
+    JumpIfSet(u32, u32),
+    /// Pops a value and raises it as an error.
+    Yeet,
+    MakeNode(u32),
+    Rm(u32),
+    Return,
+}
+
+pub struct CallSite {
+    /// Number of arguments on the stack, or 1 if they are spread from a tuple.
+    pub argc: usize,
+    pub spread: bool,
+    /// The node expression, used to name native nodes in tracebacks.
+    pub callee: Span,
+}
+
+/// Compiled code of a module or a node body.
+pub struct Chunk {
+    pub code: Vec<Op>,
+    /// Source span of each instruction, for error locations.
+    pub spans: Vec<Span>,
+    /// Variables of the scope the chunk runs in.
+    pub layout: Rc<Layout>,
+    pub consts: Vec<VarType>,
+    pub calls: Vec<CallSite>,
+    pub nodes: Vec<Rc<NodeProto>>,
+    pub modules: Vec<RmStatement>,
+}
+
+/// Compiled node definition; every evaluation of the definition creates a
+/// `CodeNode` sharing it.
+pub struct NodeProto {
+    pub name: String,
+    pub args: Vec<String>,
+    /// Slots of `args` in the layout of `chunk`.
+    pub arg_slots: Vec<u32>,
+    /// Number of leading `args` without a default.
+    pub required: usize,
+    /// Slot of the `...rest` parameter, which collects the arguments past
+    /// `args`.
+    pub rest_slot: Option<u32>,
+    pub chunk: Chunk,
+    pub span: Span,
+    /// Path of the module the node is defined in.
+    pub path: String,
+}
+
+impl NodeProto {
+    /// How many arguments the node takes, as in "1 to 2 arguments".
+    pub fn arity(&self) -> String {
+        let plural = |n: usize| if n == 1 { "argument" } else { "arguments" };
+        let (min, max) = (self.required, self.args.len());
+        if self.rest_slot.is_some() {
+            format!("at least {} {}", min, plural(min))
+        } else if min == max {
+            format!("{} {}", max, plural(max))
+        } else {
+            format!("{} to {} {}", min, max, plural(max))
+        }
+    }
+}
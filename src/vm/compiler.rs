@@ -0,0 +1,483 @@
+use std::rc::Rc;
+
+use crate::{
+    data::{
+        context::ContextRc,
+        data::{Layout, MemData},
+        lvalue::LValue,
+        variable::VarType,
+    },
+    expr::{CompareOp, Expr, ExprKind},
+    statement::{
+        expr::ExprStatement, node_def::NodeDefStatement, rm::RmStatement, CodeExecError, Statement,
+        StatementKind,
+    },
+    utils::span::Span,
+};
+
+use super::chunk::{CallSite, Chunk, NodeProto, Op};
+
+/// A loop being compiled, for `owari` and `tsugi`.
+struct Loop {
+    /// Where `tsugi` jumps to.
+    start: u32,
+    /// Whether the loop iterates with `ovo` or `qaq`, so that leaving it must
+    /// end the iteration.
+    iterates: bool,
+    /// Number of `yolo` blocks around the loop, so that leaving the blocks
+    /// inside it ends them.
+    tries: usize,
+    /// Jumps of `owari` statements, patched to the end of the loop.
+    breaks: Vec<usize>,
+}
+
+/// Translates statements into a `Chunk`. Every name the code mentions gets a
+/// slot in the layout of the scope the chunk runs in.
+pub struct Compiler {
+    layout: Layout,
+    code: Vec<Op>,
+    spans: Vec<Span>,
+    consts: Vec<VarType>,
+    calls: Vec<CallSite>,
+    nodes: Vec<Rc<NodeProto>>,
+    modules: Vec<RmStatement>,
+    loops: Vec<Loop>,
+    /// Number of `yolo` blocks being compiled.
+    tries: usize,
+}
+
+impl Compiler {
+    fn new(layout: Layout) -> Self {
+        Compiler {
+            layout,
+            code: Vec::new(),
+            spans: Vec::new(),
+            consts: Vec::new(),
+            calls: Vec::new(),
+            nodes: Vec::new(),
+            modules: Vec::new(),
+            loops: Vec::new(),
+            tries: 0,
+        }
+    }
+
+    /// Compiles `stmts` to run directly in `ctx`, e.g. the top level of a
+    /// module. The layout of the scope of `ctx` is extended to fit the chunk.
+    pub fn compile_stmts(stmts: &[Statement], ctx: &ContextRc) -> Result<Chunk, CodeExecError> {
+        let mut compiler = Compiler::for_scope(ctx);
+        for stmt in stmts {
+            compiler.stmt(stmt)?;
+        }
+        Ok(compiler.finish_in(ctx))
+    }
+
+    /// Compiles `stmts` like `compile_stmts`, except that running the chunk
+    /// returns the value of the last statement if it is an expression.
+    pub fn compile_script(stmts: &[Statement], ctx: &ContextRc) -> Result<Chunk, CodeExecError> {
+        let mut compiler = Compiler::for_scope(ctx);
+        let (value, stmts) = match stmts.split_last() {
+            Some((last, init)) => match &last.kind {
+                StatementKind::Expr(stmt) => (Some(&stmt.value), init),
+                _ => (None, stmts),
+            },
+            None => (None, stmts),
+        };
+        for stmt in stmts {
+            compiler.stmt(stmt)?;
+        }
+        if let Some(value) = value {
+            compiler.expr(value)?;
+            compiler.emit(Op::Dup, &value.span);
+            let slot = compiler.slot(ExprStatement::THAT);
+            compiler.emit(Op::That(slot), &value.span);
+            compiler.emit(Op::Return, &value.span);
+        }
+        Ok(compiler.finish_in(ctx))
+    }
+
+    fn for_scope(ctx: &ContextRc) -> Self {
+        let mess = ctx.borrow().get_mess();
+        let layout = match &*mess.borrow() {
+            MemData::Mess(mess) => (**mess.layout()).clone(),
+            _ => Layout::new(),
+        };
+        Compiler::new(layout)
+    }
+
+    fn finish_in(self, ctx: &ContextRc) -> Chunk {
+        let chunk = self.finish();
+        if let MemData::Mess(mess) = &mut *ctx.borrow().get_mess().borrow_mut() {
+            mess.adopt(&chunk.layout);
+        }
+        chunk
+    }
+
+    fn finish(self) -> Chunk {
+        Chunk {
+            code: self.code,
+            spans: self.spans,
+            layout: Rc::new(self.layout),
+            consts: self.consts,
+            calls: self.calls,
+            nodes: self.nodes,
+            modules: self.modules,
+        }
+    }
+
+    fn compile_node(def: &NodeDefStatement, span: &Span) -> Result<NodeProto, CodeExecError> {
+        let mut compiler = Compiler::new(Layout::new());
+        let arg_slots: Vec<u32> = def.params.iter().map(|p| compiler.slot(&p.name)).collect();
+        let rest_slot = def.rest.as_ref().map(|rest| compiler.slot(rest));
+        // Parameters left out of the call are not set; their defaults are
+        // evaluated first thing in the body.
+        for (param, &slot) in def.params.iter().zip(&arg_slots) {
+            if let Some(default) = &param.default {
+                let given = compiler.emit(Op::JumpIfSet(slot, 0), &default.span);
+                compiler.expr(default)?;
+                compiler.emit(Op::Store(slot), &default.span);
+                compiler.patch(given);
+            }
+        }
+        compiler.stmt(&def.body)?;
+        Ok(NodeProto {
+            name: def.name.clone(),
+            args: def.params.iter().map(|p| p.name.clone()).collect(),
+            arg_slots,
+            required: def.params.iter().filter(|p| p.default.is_none()).count(),
+            rest_slot,
+            chunk: compiler.finish(),
+            span: span.clone(),
+            path: def.path.clone(),
+        })
+    }
+
+    fn slot(&mut self, name: &str) -> u32 {
+        self.layout.add(name) as u32
+    }
+
+    fn emit(&mut self, op: Op, span: &Span) -> usize {
+        self.code.push(op);
+        self.spans.push(span.clone());
+        self.code.len() - 1
+    }
+
+    fn here(&self) -> u32 {
+        self.code.len() as u32
+    }
+
+    /// Jumps if the condition just compiled is false. A comparison is fused
+    /// with the jump, keeping its span for errors.
+    fn jump_if_false(&mut self, span: &Span) -> usize {
+        match self.code.last() {
+            Some(&Op::Cmp(op)) => {
+                let at = self.code.len() - 1;
+                self.code[at] = Op::JumpUnless(op, 0);
+                at
+            }
+            _ => self.emit(Op::JumpIfFalse(0), span),
+        }
+    }
+
+    /// Points the jump at `at` to the next instruction.
+    fn patch(&mut self, at: usize) {
+        let target = self.here();
+        self.code[at] = match self.code[at] {
+            Op::Jump(_) => Op::Jump(target),
+            Op::JumpIfFalse(_) => Op::JumpIfFalse(target),
+            Op::JumpUnless(op, _) => Op::JumpUnless(op, target),
+            Op::Maybe(_) => Op::Maybe(target),
+            Op::IterNext(_) => Op::IterNext(target),
+            Op::Try(_) => Op::Try(target),
+            Op::JumpIfSet(slot, _) => Op::JumpIfSet(slot, target),
+            op => panic!("patch: not a jump: {:?}", op),
+        };
+    }
+
+    fn constant(&mut self, value: VarType, span: &Span) {
+        self.consts.push(value);
+        let idx = self.consts.len() as u32 - 1;
+        self.emit(Op::Const(idx), span);
+    }
+
+    fn stmt(&mut self, stmt: &Statement) -> Result<(), CodeExecError> {
+        let span = &stmt.span;
+        match &stmt.kind {
+            StatementKind::Rm(rm) => {
+                self.modules.push(rm.clone());
+                self.emit(Op::Rm(self.modules.len() as u32 - 1), span);
+            }
+            StatementKind::Ass(ass) => {
+                self.expr(&ass.rhs)?;
+                if !matches!(
+                    ass.op,
+                    CompareOp::Equal | CompareOp::GreaterEqual | CompareOp::LessEqual
+                ) {
+                    self.emit(Op::Assign(ass.op), span);
+                }
+                self.assign(&ass.lhs, span)?;
+            }
+            StatementKind::Ret(ret) => {
+                self.expr(&ret.value)?;
+                self.emit(Op::Return, span);
+            }
+            StatementKind::Break => {
+                let Some(innermost) = self.loops.last() else {
+                    return Err(Compiler::outside_loop("owari", span));
+                };
+                self.end_tries(innermost.tries, span);
+                let innermost = self.loops.last().unwrap();
+                if innermost.iterates {
+                    self.emit(Op::IterEnd, span);
+                }
+                let jump = self.emit(Op::Jump(0), span);
+                self.loops.last_mut().unwrap().breaks.push(jump);
+            }
+            StatementKind::Continue => {
+                let Some(innermost) = self.loops.last() else {
+                    return Err(Compiler::outside_loop("tsugi", span));
+                };
+                let start = innermost.start;
+                self.end_tries(innermost.tries, span);
+                self.emit(Op::Jump(start), span);
+            }
+            StatementKind::If(stmt) => {
+                self.expr(&stmt.cond)?;
+                let jump = self.jump_if_false(span);
+                self.stmt(&stmt.body)?;
+                match &stmt.else_body {
+                    Some(else_body) => {
+                        let end = self.emit(Op::Jump(0), span);
+                        self.patch(jump);
+                        self.stmt(else_body)?;
+                        self.patch(end);
+                    }
+                    None => self.patch(jump),
+                }
+            }
+            StatementKind::Loli(stmt) => {
+                let start = self.here();
+                self.expr(&stmt.cond)?;
+                let jump = self.jump_if_false(span);
+                self.body(&stmt.body, start, false)?;
+                self.emit(Op::Jump(start), span);
+                self.patch(jump);
+                self.patch_breaks();
+            }
+            StatementKind::Ovo(stmt) => {
+                self.expr(&stmt.obj)?;
+                self.emit(Op::IterKeys, span);
+                self.iterate(&[LValue::Identifier(stmt.var.clone())], &stmt.body, span)?;
+            }
+            StatementKind::Qaq(stmt) => {
+                self.expr(&stmt.obj)?;
+                self.emit(Op::IterList, span);
+                self.iterate(&stmt.vars, &stmt.body, span)?;
+            }
+            StatementKind::Maybe(stmt) => {
+                let jump = self.emit(Op::Maybe(0), span);
+                self.stmt(&stmt.body)?;
+                self.patch(jump);
+            }
+            StatementKind::Try(stmt) => {
+                let handler = self.emit(Op::Try(0), span);
+                self.tries += 1;
+                self.stmt(&stmt.body)?;
+                self.tries -= 1;
+                self.emit(Op::EndTry, span);
+                let end = self.emit(Op::Jump(0), span);
+                self.patch(handler);
+                let slot = self.slot(&stmt.var);
+                self.emit(Op::Store(slot), span);
+                self.stmt(&stmt.handler)?;
+                self.patch(end);
+            }
+            StatementKind::Yeet(stmt) => {
+                self.expr(&stmt.value)?;
+                self.emit(Op::Yeet, span);
+            }
+            StatementKind::NodeDef(def) => {
+                let proto = Compiler::compile_node(def, span)?;
+                self.nodes.push(Rc::new(proto));
+                self.emit(Op::MakeNode(self.nodes.len() as u32 - 1), span);
+                let slot = self.slot(&def.name);
+                self.emit(Op::Store(slot), span);
+            }
+            StatementKind::Expr(stmt) => {
+                self.expr(&stmt.value)?;
+                let slot = self.slot(ExprStatement::THAT);
+                self.emit(Op::That(slot), span);
+            }
+            StatementKind::Stmts(stmts) => {
+                for stmt in &stmts.stmts {
+                    self.stmt(stmt)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Pops a value and assigns it to `lhs`. Several lvalues take the items of
+    /// a tuple of the same length, in order.
+    fn assign(&mut self, lhs: &[LValue], span: &Span) -> Result<(), CodeExecError> {
+        if lhs.len() > 1 {
+            self.emit(Op::Unpack(lhs.len() as u32), span);
+        }
+        for lvalue in lhs {
+            match lvalue {
+                LValue::Identifier(name) => {
+                    let slot = self.slot(name);
+                    self.emit(Op::Store(slot), span);
+                }
+                LValue::MemberExpr(member) => {
+                    self.key(&member.lhs)?;
+                    self.expr(&member.rhs)?;
+                    self.emit(Op::SetMember, span);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn iterate(
+        &mut self,
+        vars: &[LValue],
+        body: &Statement,
+        span: &Span,
+    ) -> Result<(), CodeExecError> {
+        let start = self.here();
+        let next = self.emit(Op::IterNext(0), span);
+        self.assign(vars, span)?;
+        self.body(body, start, true)?;
+        self.emit(Op::Jump(start), span);
+        self.patch(next);
+        self.patch_breaks();
+        Ok(())
+    }
+
+    /// Compiles the body of a loop starting at `start`. The loop must be
+    /// closed with `patch_breaks` once its end is emitted.
+    fn body(&mut self, body: &Statement, start: u32, iterates: bool) -> Result<(), CodeExecError> {
+        self.loops.push(Loop {
+            start,
+            iterates,
+            tries: self.tries,
+            breaks: Vec::new(),
+        });
+        self.stmt(body)
+    }
+
+    /// Points the `owari` jumps of the innermost loop past its end.
+    fn patch_breaks(&mut self) {
+        let innermost = self.loops.pop().expect("patch_breaks: not in a loop");
+        for jump in innermost.breaks {
+            self.patch(jump);
+        }
+    }
+
+    /// Ends the `yolo` blocks opened since `tries` were open, before jumping
+    /// out of them.
+    fn end_tries(&mut self, tries: usize, span: &Span) {
+        for _ in tries..self.tries {
+            self.emit(Op::EndTry, span);
+        }
+    }
+
+    fn outside_loop(keyword: &str, span: &Span) -> CodeExecError {
+        CodeExecError::new_str(format!("{} outside of a loop", keyword)).at(span)
+    }
+
+    /// Compiles the key of a member expression: a bare identifier is the key
+    /// itself, anything else is evaluated.
+    fn key(&mut self, key: &Expr) -> Result<(), CodeExecError> {
+        match &key.kind {
+            ExprKind::Identifier(id) => self.constant(VarType::String(id.name.clone()), &key.span),
+            ExprKind::Bracket(bracket) => {
+                if bracket.values.len() != 1 {
+                    return Err(CodeExecError::new_str(format!(
+                        "Cannot index with {} values",
+                        bracket.values.len()
+                    ))
+                    .at(&key.span));
+                }
+                self.expr(&bracket.values[0])?;
+            }
+            _ => self.expr(key)?,
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<(), CodeExecError> {
+        let span = &expr.span;
+        match &expr.kind {
+            ExprKind::Literal(lit) => self.constant(lit.value.clone(), span),
+            ExprKind::Lol => {
+                self.emit(Op::Lol, span);
+            }
+            ExprKind::Identifier(id) => {
+                let slot = self.slot(&id.name);
+                self.emit(Op::Load(slot), span);
+            }
+            ExprKind::Add(e) => self.binary(&e.lhs, &e.rhs, Op::Add, span)?,
+            ExprKind::Sub(e) => self.binary(&e.lhs, &e.rhs, Op::Sub, span)?,
+            ExprKind::Mul(e) => self.binary(&e.lhs, &e.rhs, Op::Mul, span)?,
+            ExprKind::Div(e) => self.binary(&e.lhs, &e.rhs, Op::Div, span)?,
+            ExprKind::Mod(e) => self.binary(&e.lhs, &e.rhs, Op::Mod, span)?,
+            ExprKind::Cmp(e) => self.binary(&e.lhs, &e.rhs, Op::Cmp(e.op), span)?,
+            ExprKind::Not(e) => {
+                self.expr(&e.value)?;
+                self.emit(Op::Not, span);
+            }
+            ExprKind::Neg(e) => {
+                self.expr(&e.value)?;
+                self.emit(Op::Neg, span);
+            }
+            ExprKind::Tuple(tuple) | ExprKind::Bracket(tuple) => {
+                for value in &tuple.values {
+                    self.expr(value)?;
+                }
+                self.emit(Op::Tuple(tuple.values.len() as u32), span);
+            }
+            ExprKind::Member(member) => {
+                self.key(&member.lhs)?;
+                self.expr(&member.rhs)?;
+                self.emit(Op::GetMember, span);
+            }
+            ExprKind::NodeCall(call) => {
+                self.expr(&call.node)?;
+                // A literal tuple is spread at compile time, anything else at
+                // run time if it turns out to be a tuple.
+                let (argc, spread) = match &call.args.kind {
+                    ExprKind::Tuple(tuple) => {
+                        for value in &tuple.values {
+                            self.expr(value)?;
+                        }
+                        (tuple.values.len(), false)
+                    }
+                    _ => {
+                        self.expr(&call.args)?;
+                        (1, true)
+                    }
+                };
+                self.calls.push(CallSite {
+                    argc,
+                    spread,
+                    callee: call.node.span.clone(),
+                });
+                self.emit(Op::Call(self.calls.len() as u32 - 1), span);
+            }
+            ExprKind::Node(def) => {
+                let proto = Compiler::compile_node(def, span)?;
+                self.nodes.push(Rc::new(proto));
+                self.emit(Op::MakeNode(self.nodes.len() as u32 - 1), span);
+            }
+        }
+        Ok(())
+    }
+
+    fn binary(&mut self, lhs: &Expr, rhs: &Expr, op: Op, span: &Span) -> Result<(), CodeExecError> {
+        self.expr(lhs)?;
+        self.expr(rhs)?;
+        self.emit(op, span);
+        Ok(())
+    }
+}
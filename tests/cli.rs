@@ -0,0 +1,35 @@
+use std::{env, fs, process::Command};
+
+#[test]
+fn test_builtin_code_module_outside_repo() {
+    let dir = env::temp_dir().join(format!("lIIl-cli-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("main.lIIl"),
+        "rm ut.\nrm ll.\n\nmake lst lol.\nlst | 7 >> hasu@ll.\n0@lst | 7 >> eq@ut.\n",
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_lIIl"))
+        .arg("main.lIIl")
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_invalid_env_seed() {
+    let output = Command::new(env!("CARGO_BIN_EXE_lIIl"))
+        .arg("tests/programs/gcd.lIIl")
+        .env("LIIL_SEED", "abc")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("invalid value 'abc' for 'LIIL_SEED'"), "{}", stderr);
+}